@@ -0,0 +1,252 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Client of ssh-agent, which is found through `SSH_AUTH_SOCK`
+//!
+//! Secret keys of instances are added with the comment `rerobots:ID`, so
+//! that they can be found and removed when the instance is terminated.
+
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use crate::sshkey::{instance_comment, Decoder, Encoder, SecretKey};
+
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENT_SUCCESS: u8 = 6;
+const SSH2_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH2_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH2_AGENTC_ADD_IDENTITY: u8 = 17;
+const SSH2_AGENTC_REMOVE_IDENTITY: u8 = 18;
+const SSH2_AGENTC_ADD_ID_CONSTRAINED: u8 = 25;
+const SSH_AGENT_CONSTRAIN_LIFETIME: u8 = 1;
+
+/// Upper bound on the size of agent messages, as in OpenSSH
+const MAX_MESSAGE_LEN: usize = 256 * 1024;
+
+trait Stream: Read + Write {}
+
+impl<T: Read + Write> Stream for T {}
+
+/// Whether an agent is running, according to the environment
+pub fn is_available() -> bool {
+    std::env::var_os("SSH_AUTH_SOCK").is_some_and(|s| !s.is_empty())
+}
+
+/// Key held by the agent
+pub struct Identity {
+    /// Public key in the SSH wire encoding
+    pub public_blob: Vec<u8>,
+    pub comment: String,
+}
+
+pub struct Agent {
+    stream: Box<dyn Stream>,
+}
+
+impl Agent {
+    /// Connect to the agent at `SSH_AUTH_SOCK`.
+    #[cfg(unix)]
+    pub fn connect() -> Result<Agent, Box<dyn std::error::Error>> {
+        let path = std::env::var_os("SSH_AUTH_SOCK")
+            .filter(|s| !s.is_empty())
+            .ok_or("no ssh-agent: SSH_AUTH_SOCK is not set")?;
+        let stream = std::os::unix::net::UnixStream::connect(&path).map_err(|err| {
+            format!(
+                "cannot connect to ssh-agent at {}: {err}",
+                std::path::Path::new(&path).display()
+            )
+        })?;
+        Ok(Agent {
+            stream: Box::new(stream),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn connect() -> Result<Agent, Box<dyn std::error::Error>> {
+        Err("ssh-agent is supported only on Unix-like systems".into())
+    }
+
+    fn request(&mut self, message: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.stream
+            .write_all(&(message.len() as u32).to_be_bytes())?;
+        self.stream.write_all(message)?;
+        self.stream.flush()?;
+        let mut len = [0; 4];
+        self.stream.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len == 0 || len > MAX_MESSAGE_LEN {
+            return Err("invalid message from ssh-agent".into());
+        }
+        let mut response = vec![0; len];
+        self.stream.read_exact(&mut response)?;
+        Ok(response)
+    }
+
+    /// Send request for which the agent answers with success or failure.
+    fn request_success(
+        &mut self,
+        message: &[u8],
+        action: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self.request(message)?.first() {
+            Some(&SSH_AGENT_SUCCESS) => Ok(()),
+            Some(&SSH_AGENT_FAILURE) => Err(format!("ssh-agent refused to {action}").into()),
+            _ => Err("unexpected response from ssh-agent".into()),
+        }
+    }
+
+    /// Add secret key. If `lifetime` is given, then the agent deletes the
+    /// key after that time.
+    pub fn add(
+        &mut self,
+        key: &SecretKey,
+        comment: &str,
+        lifetime: Option<Duration>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut message = Encoder::default();
+        match lifetime {
+            Some(_) => message.u8(SSH2_AGENTC_ADD_ID_CONSTRAINED),
+            None => message.u8(SSH2_AGENTC_ADD_IDENTITY),
+        };
+        key.encode_private(&mut message)?;
+        message.string(comment.as_bytes());
+        if let Some(lifetime) = lifetime {
+            let seconds = u32::try_from(lifetime.as_secs()).unwrap_or(u32::MAX).max(1);
+            message.u8(SSH_AGENT_CONSTRAIN_LIFETIME).u32(seconds);
+        }
+        self.request_success(&message.0, "add key")
+    }
+
+    pub fn identities(&mut self) -> Result<Vec<Identity>, Box<dyn std::error::Error>> {
+        let response = self.request(&[SSH2_AGENTC_REQUEST_IDENTITIES])?;
+        let mut response = Decoder(&response);
+        if response.u8()? != SSH2_AGENT_IDENTITIES_ANSWER {
+            return Err("unexpected response from ssh-agent".into());
+        }
+        let count = response.u32()?;
+        let mut identities = vec![];
+        for _ in 0..count {
+            let public_blob = response.string()?.to_vec();
+            let comment = String::from_utf8_lossy(response.string()?).to_string();
+            identities.push(Identity {
+                public_blob,
+                comment,
+            });
+        }
+        Ok(identities)
+    }
+
+    /// Remove key, given as public key in the SSH wire encoding.
+    pub fn remove(&mut self, public_blob: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut message = Encoder::default();
+        message.u8(SSH2_AGENTC_REMOVE_IDENTITY).string(public_blob);
+        self.request_success(&message.0, "remove key")
+    }
+}
+
+/// Whether the agent has the secret key of instance
+pub fn has_instance_key(instance_id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let comment = instance_comment(instance_id);
+    Ok(Agent::connect()?
+        .identities()?
+        .iter()
+        .any(|i| i.comment == comment))
+}
+
+/// Add secret key of instance, which the agent deletes after `lifetime`.
+pub fn add_instance_key(
+    instance_id: &str,
+    key: &SecretKey,
+    lifetime: Option<Duration>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Agent::connect()?.add(key, &instance_comment(instance_id), lifetime)
+}
+
+/// Remove secret keys of instance. Return how many were removed.
+pub fn remove_instance_keys(instance_id: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    let comment = instance_comment(instance_id);
+    let mut agent = Agent::connect()?;
+    let mut removed = 0;
+    for identity in agent.identities()?.iter().filter(|i| i.comment == comment) {
+        agent.remove(&identity.public_blob)?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    use super::Agent;
+    use crate::sshkey::{Decoder, SecretKey};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    /// Read one request and answer it with `response`.
+    fn answer_once(mut stream: UnixStream, response: Vec<u8>) -> std::thread::JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let mut len = [0; 4];
+            stream.read_exact(&mut len).expect("request length");
+            let mut request = vec![0; u32::from_be_bytes(len) as usize];
+            stream.read_exact(&mut request).expect("request");
+            stream
+                .write_all(&(response.len() as u32).to_be_bytes())
+                .expect("response length");
+            stream.write_all(&response).expect("response");
+            request
+        })
+    }
+
+    #[test]
+    fn add_with_lifetime() -> TestResult {
+        let (client, server) = UnixStream::pair()?;
+        let responder = answer_once(server, vec![super::SSH_AGENT_SUCCESS]);
+        let mut agent = Agent {
+            stream: Box::new(client),
+        };
+        let key = SecretKey::generate_ed25519()?;
+        agent.add(
+            &key,
+            "rerobots:abc",
+            Some(std::time::Duration::from_secs(3600)),
+        )?;
+
+        let request = responder.join().map_err(|_| "responder panicked")?;
+        let mut request = Decoder(&request);
+        assert_eq!(request.u8()?, super::SSH2_AGENTC_ADD_ID_CONSTRAINED);
+        assert_eq!(request.string()?, b"ssh-ed25519");
+        assert_eq!(request.string()?.len(), 32);
+        assert_eq!(request.string()?.len(), 64);
+        assert_eq!(request.string()?, b"rerobots:abc");
+        assert_eq!(request.u8()?, super::SSH_AGENT_CONSTRAIN_LIFETIME);
+        assert_eq!(request.u32()?, 3600);
+        assert!(request.0.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn refused() -> TestResult {
+        let (client, server) = UnixStream::pair()?;
+        let responder = answer_once(server, vec![super::SSH_AGENT_FAILURE]);
+        let mut agent = Agent {
+            stream: Box::new(client),
+        };
+        let err = agent.remove(b"key").err().ok_or("remove should fail")?;
+        assert_eq!(err.to_string(), "ssh-agent refused to remove key");
+        responder.join().map_err(|_| "responder panicked")?;
+        Ok(())
+    }
+}
@@ -12,23 +12,43 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::fs::{File, OpenOptions};
-use std::io::prelude::*;
-
-#[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
-
-use clap::{Arg, SubCommand};
-
-use rerobots::client;
-use rerobots::client::TokenClaims;
-
-#[derive(PartialEq)]
-enum DefaultConfirmAnswer {
-    Yes,
-    No,
-    None,
-}
+use std::io::Write;
+
+use clap::Arg;
+
+mod addon;
+mod cam;
+mod exec;
+mod extend;
+mod get_ssh_key;
+mod info;
+mod isready;
+mod keys;
+mod label;
+mod launch;
+mod list;
+mod login;
+mod mistyproxy;
+mod pull;
+mod push;
+mod reservations;
+mod reserve;
+mod search;
+mod shell;
+mod ssh;
+mod terminate;
+mod token;
+mod version;
+mod vpn;
+mod wdinfo;
+
+use crate::agent;
+use crate::claims::Claims;
+use crate::config::{Config, Profile};
+use crate::logging::{self, LogFormat};
+use crate::state::{self, State};
+use crate::{credentials, duration, format_timestamp, instances_info, TokenSource};
+use crate::{Context, DefaultConfirmAnswer, PrintingFormat};
 
 pub struct CliError {
     pub msg: Option<String>,
@@ -63,6 +83,15 @@ impl From<&str> for CliError {
     }
 }
 
+impl From<String> for CliError {
+    fn from(value: String) -> Self {
+        CliError {
+            msg: Some(value),
+            exitcode: 1,
+        }
+    }
+}
+
 impl CliError {
     fn new<S: ToString>(msg: S, exitcode: i32) -> Result<(), CliError> {
         Err(CliError {
@@ -93,555 +122,746 @@ impl CliError {
     }
 }
 
-#[derive(PartialEq, Debug)]
-enum PrintingFormat {
-    Default,
-    Yaml,
-    Json,
-}
-
-fn search_subcommand(
+fn context_from_matches(
     matches: &clap::ArgMatches,
-    api_token: Option<String>,
-) -> Result<(), CliError> {
-    let query = matches.value_of("query");
-    let type_constraint = if matches.is_present("with_user_provided") {
-        None
-    } else {
-        Some(vec!["!user_provided"])
-    };
-    let payload = match client::api_search(query, type_constraint.as_ref(), api_token) {
-        Ok(p) => p,
-        Err(err) => return CliError::new_std(err, 1),
+    profile_name: Option<String>,
+    profile: Profile,
+    uses_api_token: bool,
+) -> Result<Context, CliError> {
+    let pformat = match matches.value_of("printformat") {
+        Some(given_pformat) => {
+            let given_pformat_lower = given_pformat.to_lowercase();
+            if given_pformat_lower == "json" {
+                PrintingFormat::Json
+            } else if given_pformat_lower == "yaml" {
+                PrintingFormat::Yaml
+            } else {
+                return Err(CliError {
+                    msg: Some(format!("unrecognized format: {given_pformat}")),
+                    exitcode: 1,
+                });
+            }
+        }
+        None => PrintingFormat::Default,
     };
-    for wd in payload["workspace_deployments"]
-        .as_array()
-        .expect("workspace_deployments should be array")
-        .iter()
-    {
-        let wd = wd
-            .as_str()
-            .expect("Elements of workspace_deployments should be strings");
-        let wtype = payload["info"][wd]["type"]
-            .as_str()
-            .expect("info.wd.type should be string");
-        println!("{wd}    {wtype}");
+
+    let (api_token, token_source) =
+        find_token(matches, profile_name.as_deref(), &profile, uses_api_token)?;
+
+    let mut ctx = Context::new(api_token);
+    ctx.token_source = token_source;
+    ctx.profile_name = profile_name;
+    ctx.profile = profile;
+    ctx.client = ctx.client.with_trace(matches.is_present("trace"));
+    if let Some(dir) = matches.value_of("dump_http") {
+        ctx.client = ctx.client.with_dump_dir(dir);
     }
-    Ok(())
+    ctx.pformat = pformat;
+    ctx.default_confirm = decide_default_confirmation(matches);
+    Ok(ctx)
 }
 
-fn list_subcommand(matches: &clap::ArgMatches, api_token: Option<String>) -> Result<(), CliError> {
-    let be_quiet = matches.is_present("quiet");
-    let include_terminated = matches.is_present("include_terminated");
-    let payload = match client::api_instances(api_token, include_terminated) {
-        Ok(p) => p,
-        Err(err) => return CliError::new_std(err, 1),
-    };
-    if !be_quiet {
-        println!("instance\t\t\t\tworkspace deployment");
+/// Command that can be selected from the command line, e.g., `rerobots list`
+///
+/// To add a new subcommand, implement this trait and append it to the list
+/// returned by `subcommands()`.
+pub trait Subcommand {
+    /// Name of the subcommand, as typed on the command line
+    fn name(&self) -> &'static str;
+
+    /// Definition of arguments, help text, etc.
+    fn app(&self) -> clap::App<'static, 'static>;
+
+    /// Execute the subcommand using matched arguments and the shared context
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError>;
+
+    /// Whether the subcommand with `matches` uses the API token. If not, then
+    /// the credential store is not opened.
+    fn uses_api_token(&self, _matches: &clap::ArgMatches) -> bool {
+        true
     }
-    for (j, inst) in payload["workspace_instances"]
-        .as_array()
-        .expect("workspace_instances should be array")
-        .iter()
-        .enumerate()
-    {
-        let inst = inst
-            .as_str()
-            .expect("Elements of workspace_instances should be strings");
-        if be_quiet {
-            println!("{inst}");
-        } else {
-            let wdeployment_id = &payload["workspace_deployments"]
-                .as_array()
-                .expect("workspace_deployments should be array")[j]
-                .as_str()
-                .expect("Elements of workspace_deployments should be strings");
-            println!("{inst}\t{wdeployment_id}");
+
+    /// Whether the API token is checked, e.g., for expiration, before running
+    /// the subcommand with `matches`. By default, it is checked if it is used.
+    fn checks_api_token(&self, matches: &clap::ArgMatches) -> bool {
+        self.uses_api_token(matches)
+    }
+}
+
+fn subcommands() -> Vec<Box<dyn Subcommand>> {
+    vec![
+        Box::new(version::Version),
+        Box::new(search::Search),
+        Box::new(list::List),
+        Box::new(info::Info),
+        Box::new(get_ssh_key::GetSshKey),
+        Box::new(keys::Keys),
+        Box::new(wdinfo::Wdinfo),
+        Box::new(launch::Launch),
+        Box::new(reserve::Reserve),
+        Box::new(reservations::Reservations),
+        Box::new(login::Login),
+        Box::new(terminate::Terminate),
+        Box::new(extend::Extend),
+        Box::new(isready::Isready),
+        Box::new(ssh::Ssh),
+        Box::new(addon::Addon),
+        Box::new(cam::Cam),
+        Box::new(vpn::Vpn),
+        Box::new(mistyproxy::Mistyproxy),
+        Box::new(push::Push),
+        Box::new(pull::Pull),
+        Box::new(exec::Exec),
+        Box::new(shell::Shell),
+        Box::new(label::Label),
+        Box::new(token::Token),
+    ]
+}
+
+fn global_args(app: clap::App<'static, 'static>) -> clap::App<'static, 'static> {
+    app.arg(Arg::with_name("version")
+             .short("V")
+             .long("version")
+             .help("Prints version number and exits"))
+        .arg(Arg::with_name("verbose")
+             .short("v")
+             .long("verbose")
+             .help("Increases verboseness level of logs; ignored if RUST_LOG is defined"))
+        .arg(Arg::with_name("log_file")
+             .long("log-file")
+             .value_name("PATH")
+             .help("write logs to PATH instead of stderr; the file is rotated when it becomes large"))
+        .arg(Arg::with_name("log_format")
+             .long("log-format")
+             .value_name("FORMAT")
+             .help("format of log messages; options: text , json (default text)"))
+        .arg(Arg::with_name("profile")
+             .long("profile")
+             .value_name("NAME")
+             .help("name of profile in configuration file to use; if not given, use REROBOTS_PROFILE environment variable or default profile"))
+        .arg(Arg::with_name("trace")
+             .long("trace")
+             .help("Prints method, URL, status, latency, and request ID of each API request to stderr"))
+        .arg(Arg::with_name("dump_http")
+             .long("dump-http")
+             .value_name("DIR")
+             .help("write each API request and response, with secrets removed, to a file in DIR"))
+        .arg(Arg::with_name("printformat")
+             .long("format")
+             .value_name("FORMAT")
+             .help("output formatting; options: YAML , JSON"))
+        .arg(Arg::with_name("apitoken")
+             .short("-t")
+             .value_name("FILE")
+             .help("plaintext file containing API token; with this flag, the REROBOTS_API_TOKEN environment variable and stored tokens are ignored"))
+        .arg(Arg::with_name("assume_yes")
+             .short("y")
+             .help("assume \"yes\" for any questions required to execute the command; otherwise, interactive prompts will appear to confirm actions as needed"))
+        .arg(Arg::with_name("assume_no")
+             .short("n")
+             .help("assume \"no\" for any questions required to execute the command; this can prevent destructive actions, e.g., overwriting a local file"))
+}
+
+fn read_token_file(fname: &str) -> Result<String, CliError> {
+    if !std::path::Path::new(fname).exists() {
+        return Err(CliError {
+            msg: Some(format!("Error: {fname} does not exist")),
+            exitcode: 1,
+        });
+    }
+    if let Some(problem) = credentials::check_permissions(std::path::Path::new(fname)) {
+        eprintln!("warning: {problem}");
+    }
+    match std::fs::read_to_string(fname) {
+        Ok(s) => Ok(s.trim().to_string()),
+        Err(err) => Err(CliError {
+            msg: Some(format!("{err}")),
+            exitcode: 1,
+        }),
+    }
+}
+
+fn stored_token(name: &str, profile: &Profile) -> Option<(String, &'static str)> {
+    let result = credentials::open(profile.credential_backend.as_deref())
+        .and_then(|store| Ok(store.get(name)?.map(|token| (token, store.name()))));
+    match result {
+        Ok(found) => found,
+        Err(err) => {
+            eprintln!("warning: cannot read stored API token: {err}");
+            None
         }
     }
-    Ok(())
 }
 
-fn info_subcommand(
+/// Find API token from, in order of precedence, `-t`, the environment variable
+/// `REROBOTS_API_TOKEN`, `api_token_file` of the profile, or the credential store.
+///
+/// The credential store is opened only if `use_store`, because opening it can
+/// prompt, e.g., for a passphrase.
+fn find_token(
     matches: &clap::ArgMatches,
-    api_token: Option<String>,
-    pformat: PrintingFormat,
-) -> Result<(), CliError> {
-    let instance_id = matches.value_of("instance_id");
-    let mut payload = match client::api_instance_info(instance_id, api_token) {
-        Ok(p) => p,
-        Err(err) => return CliError::new_std(err, 1),
+    profile_name: Option<&str>,
+    profile: &Profile,
+    use_store: bool,
+) -> Result<(Option<String>, Option<TokenSource>), CliError> {
+    if let Some(fname) = matches.value_of("apitoken") {
+        let api_token = read_token_file(fname)?;
+        return Ok((Some(api_token), Some(TokenSource::Flag(fname.into()))));
+    }
+    if let Ok(api_token) = std::env::var("REROBOTS_API_TOKEN") {
+        if !api_token.is_empty() {
+            return Ok((Some(api_token), Some(TokenSource::Environment)));
+        }
+    }
+    let name = profile_name.unwrap_or("default");
+    if let Some(path) = &profile.api_token_file {
+        let api_token = read_token_file(&path.to_string_lossy())?;
+        return Ok((
+            Some(api_token),
+            Some(TokenSource::Profile(name.into(), path.clone())),
+        ));
+    }
+    if use_store {
+        if let Some((api_token, backend)) = stored_token(name, profile) {
+            return Ok((
+                Some(api_token),
+                Some(TokenSource::Stored(name.into(), backend)),
+            ));
+        }
+    }
+    Ok((None, None))
+}
+
+const DEFAULT_TOKEN_EXPIRY_WARNING: &str = "7d";
+
+/// Check the API token before it is used.
+///
+/// An error is returned if the token is expired. A warning is printed if it
+/// expires within the window given by `REROBOTS_TOKEN_EXPIRY_WARNING` or
+/// `token_expiry_warning` of the profile (default 7d). Tokens that cannot be
+/// decoded are not checked here, and the API will reject them if invalid.
+fn check_token(ctx: &Context) -> Result<(), CliError> {
+    let api_token = match &ctx.api_token {
+        Some(tok) => tok,
+        None => return Ok(()),
     };
-    payload["url"] = format!(
-        "https://rerobots.net/instance/{}",
-        payload["id"].as_str().expect("id should be string")
-    )
-    .into();
-    if pformat == PrintingFormat::Yaml {
-        println!(
-            "{}",
-            serde_yaml::to_string(&payload).expect("Instance info can be serialized to YAML")
+    let source = match &ctx.token_source {
+        Some(s) => s.to_string(),
+        None => "(unknown)".into(),
+    };
+    info!("using API token from {}", source);
+    let claims = match Claims::decode(api_token) {
+        Ok(c) => c,
+        Err(err) => {
+            debug!("cannot check API token: {}", err);
+            return Ok(());
+        }
+    };
+    let (expiration, remaining) = match (claims.expiration, claims.remaining()) {
+        (Some(exp), Some(remaining)) => (exp, remaining),
+        _ => return Ok(()),
+    };
+    if claims.is_expired() {
+        return CliError::new(
+            format!(
+                "Error: API token from {source} expired at {}; get a new token at https://rerobots.net/tokens",
+                format_timestamp(expiration)
+            ),
+            1,
         );
-    } else {
-        // pformat == PrintingFormat::Json
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&payload)
-                .expect("Instance info can be serialized to JSON")
+    }
+    let window = std::env::var("REROBOTS_TOKEN_EXPIRY_WARNING")
+        .ok()
+        .or_else(|| ctx.profile.token_expiry_warning.clone())
+        .unwrap_or_else(|| DEFAULT_TOKEN_EXPIRY_WARNING.into());
+    let window = match duration::parse(&window) {
+        Ok(w) => w,
+        Err(err) => return CliError::new(format!("token expiry warning: {err}"), 1),
+    };
+    if remaining < window {
+        eprintln!(
+            "warning: API token from {source} expires in {} (at {})",
+            duration::format(remaining),
+            format_timestamp(expiration)
         );
     }
     Ok(())
 }
 
-fn get_sshkey_subcommand(
+fn load_profile(
     matches: &clap::ArgMatches,
-    api_token: Option<String>,
-    default_confirm: DefaultConfirmAnswer,
-) -> Result<(), CliError> {
-    let instance_id = matches.value_of("instance_id");
-
-    let path = matches.value_of("secret_key_path").unwrap_or("key.pem");
-    if std::path::Path::new(path).exists() && default_confirm != DefaultConfirmAnswer::Yes {
-        if default_confirm == DefaultConfirmAnswer::No {
-            return CliError::new(format!("Error: {path} already exists"), 1);
-        }
-        let prompt = format!("Overwrite existing file at {path} with new secret key? [y/N]");
-        loop {
-            print!("{prompt} ");
-            std::io::stdout()
-                .flush()
-                .expect("Flush of stdout should succeed");
-            let mut choice = String::new();
-            match std::io::stdin().read_line(&mut choice) {
-                Ok(_) => {
-                    choice.make_ascii_lowercase();
-                    let choicel = choice.trim();
-                    if choicel == "n" || choicel == "no" || choicel.is_empty() {
-                        return CliError::newrc(1);
-                    } else if choicel == "y" || choicel == "yes" {
-                        break;
-                    }
-                }
-                Err(err) => {
-                    return CliError::new_stdio(err, 1);
-                }
-            }
+) -> Result<(Option<String>, Profile), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    match config.active_profile_name(matches.value_of("profile")) {
+        Some(name) => {
+            let profile = config.profile(&name)?;
+            Ok((Some(name), profile))
         }
+        None => Ok((None, Profile::default())),
     }
+}
 
-    let key = match client::get_instance_sshkey(instance_id, api_token) {
-        Ok(k) => k,
-        Err(err) => return CliError::new_std(err, 1),
+fn init_logging(matches: &clap::ArgMatches, profile: &Profile) -> Result<(), CliError> {
+    let default_loglevel = if matches.is_present("verbose") {
+        "info"
+    } else {
+        "warn"
     };
-
-    match write_secret_key(path, &key) {
+    let log_format = match matches
+        .value_of("log_format")
+        .or(profile.log_format.as_deref())
+    {
+        Some(f) => f.parse()?,
+        None => LogFormat::Text,
+    };
+    let log_file = match matches.value_of("log_file") {
+        Some(p) => Some(std::path::PathBuf::from(p)),
+        None => profile.log_file.clone(),
+    };
+    match logging::init(default_loglevel, log_format, log_file.as_deref()) {
         Ok(()) => Ok(()),
         Err(err) => CliError::new_std(err, 1),
     }
 }
 
-fn wdinfo_subcommand(
-    matches: &clap::ArgMatches,
-    api_token: Option<String>,
-    pformat: PrintingFormat,
-) -> Result<(), CliError> {
-    let wdeployment_id = matches
-        .value_of("wdeployment_id")
-        .ok_or("ID argument is required")?;
-    let payload = match client::api_wdeployment_info(wdeployment_id, api_token) {
-        Ok(p) => p,
-        Err(err) => return CliError::new_std(err, 1),
-    };
-    if pformat == PrintingFormat::Yaml {
-        println!(
-            "{}",
-            serde_yaml::to_string(&payload).expect("Deployment info can be serialized to YAML")
-        );
+fn decide_default_confirmation(matches: &clap::ArgMatches) -> DefaultConfirmAnswer {
+    if matches.is_present("assume_no") {
+        DefaultConfirmAnswer::No
+    } else if matches.is_present("assume_yes") {
+        DefaultConfirmAnswer::Yes
     } else {
-        // pformat == PrintingFormat::Json
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&payload)
-                .expect("Deployment info can be serialized to JSON")
-        );
+        DefaultConfirmAnswer::None
     }
-    Ok(())
 }
 
-fn terminate_subcommand(
-    matches: &clap::ArgMatches,
-    api_token: Option<String>,
-) -> Result<(), CliError> {
-    let instance_id = matches.value_of("instance_id");
-    match client::api_terminate_instance(instance_id, api_token) {
-        Ok(()) => Ok(()),
-        Err(err) => CliError::new_std(err, 1),
+/// Ask the question in `prompt`, which should end with `[y/N]`.
+///
+/// The answer can be given in advance with `-y` or `-n`.
+fn confirm(ctx: &Context, prompt: &str) -> Result<bool, CliError> {
+    match ctx.default_confirm {
+        DefaultConfirmAnswer::Yes => return Ok(true),
+        DefaultConfirmAnswer::No => return Ok(false),
+        DefaultConfirmAnswer::None => {}
+    }
+    loop {
+        print!("{prompt} ");
+        std::io::stdout()
+            .flush()
+            .expect("Flush of stdout should succeed");
+        let mut choice = String::new();
+        match std::io::stdin().read_line(&mut choice) {
+            Ok(_) => {
+                choice.make_ascii_lowercase();
+                let choicel = choice.trim();
+                if choicel == "n" || choicel == "no" || choicel.is_empty() {
+                    return Ok(false);
+                } else if choicel == "y" || choicel == "yes" {
+                    return Ok(true);
+                }
+            }
+            Err(err) => return Err(CliError::from(err.to_string())),
+        }
+    }
+}
+
+/// Ask whether to overwrite the file at `path`, if it exists.
+///
+/// If the file should not be overwritten, then the returned error stops the
+/// command.
+fn confirm_overwrite(ctx: &Context, path: &str, what: &str) -> Result<(), CliError> {
+    if !std::path::Path::new(path).exists() {
+        return Ok(());
+    }
+    if confirm(
+        ctx,
+        &format!("Overwrite existing file at {path} with {what}? [y/N]"),
+    )? {
+        Ok(())
+    } else if ctx.default_confirm == DefaultConfirmAnswer::No {
+        CliError::new(format!("Error: {path} already exists"), 1)
+    } else {
+        CliError::newrc(1)
     }
 }
 
-fn isready_subcommand(
+/// Path of secret key for SSH access to instance, if there is one
+///
+/// The key in the managed directory, as written by `get-ssh-key`, has
+/// precedence over `key.pem` in the current directory.
+fn default_secret_key(instance_id: &str) -> Option<String> {
+    if let Some(path) = crate::sshkey::instance_key_path(instance_id) {
+        if path.exists() {
+            return path.to_str().map(String::from);
+        }
+    }
+    if std::path::Path::new("key.pem").exists() {
+        Some("key.pem".into())
+    } else {
+        None
+    }
+}
+
+/// Load secret key of instance into ssh-agent until the instance expires.
+///
+/// The key is not written to any file. The lifetime given to the agent is
+/// returned, if the instance has an expiration.
+fn add_to_agent(
+    ctx: &Context,
+    instance: &serde_json::Value,
+) -> Result<Option<std::time::Duration>, Box<dyn std::error::Error>> {
+    let instance_id = instance["id"].as_str().ok_or("id should be string")?;
+    let lifetime = crate::remaining_time(instance);
+    if instance["status"] == "TERMINATED" || lifetime.is_some_and(|r| r.is_zero()) {
+        return Err("instance is not active".into());
+    }
+    let key = crate::sshkey::SecretKey::parse(&ctx.client.instance_sshkey(Some(instance_id))?)?;
+    agent::add_instance_key(instance_id, &key, lifetime)?;
+    Ok(lifetime)
+}
+
+/// Switch for selecting instances by label instead of ID, e.g., `-l project=grasping`
+fn selector_arg() -> Arg<'static, 'static> {
+    Arg::with_name("selector")
+        .short("l")
+        .long("selector")
+        .value_name("KEY=VALUE")
+        .multiple(true)
+        .number_of_values(1)
+        .conflicts_with("instance_id")
+        .help("select instances that have the label; can be given more than once")
+}
+
+/// Active instances that have all labels given with `-l`, or None if `-l` is not given
+fn select_by_labels(
     matches: &clap::ArgMatches,
-    api_token: Option<String>,
-) -> Result<(), CliError> {
-    let blocking = matches.is_present("blocking");
-    let mut instance_id = matches.value_of("instance_id").map(|s| s.to_string());
-    loop {
-        let payload = match client::api_instance_info(instance_id.clone(), api_token.clone()) {
+    ctx: &Context,
+) -> Result<Option<Vec<String>>, CliError> {
+    let given: Vec<&str> = match matches.values_of("selector") {
+        Some(v) => v.collect(),
+        None => return Ok(None),
+    };
+    let selector = state::parse_selector(&given).map_err(|err| CliError::from(err.to_string()))?;
+    let local = State::load().map_err(|err| CliError::from(err.to_string()))?;
+    let payload = ctx
+        .client
+        .instances(false)
+        .map_err(|err| CliError::from(err.to_string()))?;
+    let active: Vec<&str> = payload["workspace_instances"]
+        .as_array()
+        .ok_or("workspace_instances should be array")?
+        .iter()
+        .filter_map(|inst| inst.as_str())
+        .collect();
+    let selected: Vec<String> = with_labels(ctx, &active, &local, &selector)
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if selected.is_empty() {
+        return Err(CliError::from(format!(
+            "no active instances with labels {}",
+            state::format_labels(&selector)
+        )));
+    }
+    Ok(Some(selected))
+}
+
+/// Instances that have all labels of `selector`, in the given order
+///
+/// Labels are those in local state, which take precedence as they might have
+/// been changed with `rerobots label`, and those attached at launch, which the
+/// API keeps. Checking the API allows selecting instances that were launched
+/// elsewhere, e.g., by another CI runner with the same account. Details are
+/// only fetched for instances that local state does not match, and instances
+/// whose details cannot be fetched, e.g., because they were just terminated,
+/// are skipped with a warning.
+fn with_labels<'a>(
+    ctx: &Context,
+    instance_ids: &[&'a str],
+    local: &State,
+    selector: &state::Labels,
+) -> Vec<&'a str> {
+    let unmatched: Vec<&str> = instance_ids
+        .iter()
+        .copied()
+        .filter(|id| !local.matches(id, selector))
+        .collect();
+    let details = instances_info(&ctx.client, &unmatched);
+    let mut from_api = vec![];
+    for (instance_id, detail) in unmatched.iter().zip(details) {
+        let payload = match detail {
             Ok(p) => p,
-            Err(err) => return CliError::new_std(err, 1),
+            Err(err) => {
+                eprintln!("warning: skipping instance {instance_id}: {err}");
+                continue;
+            }
         };
-        let status = payload["status"]
-            .as_str()
-            .expect("Instance status should be string");
-        if status == "READY" {
-            return Ok(());
-        } else if status != "INIT" || !blocking {
-            return CliError::newrc(1);
+        let mut labels: state::Labels = match payload["labels"].as_object() {
+            Some(given) => given
+                .iter()
+                .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                .collect(),
+            None => state::Labels::new(),
+        };
+        labels.extend(local.labels(instance_id));
+        if selector.iter().all(|(k, v)| labels.get(k) == Some(v)) {
+            from_api.push(*instance_id);
         }
-        if instance_id.is_none() {
-            instance_id = Some(
-                payload["id"]
-                    .as_str()
-                    .expect("Instance id should be string")
-                    .to_string(),
-            );
+    }
+    instance_ids
+        .iter()
+        .copied()
+        .filter(|id| local.matches(id, selector) || from_api.contains(id))
+        .collect()
+}
+
+/// Instance ID that is given as argument or selected with `-l`
+///
+/// If neither is given, then None, and the client selects the sole active
+/// instance.
+fn instance_id_from(matches: &clap::ArgMatches, ctx: &Context) -> Result<Option<String>, CliError> {
+    match select_by_labels(matches, ctx)? {
+        Some(selected) => {
+            if selected.len() > 1 {
+                Err(CliError::from(format!(
+                    "ambiguous command because more than one active instance matches: {}",
+                    selected.join(", ")
+                )))
+            } else {
+                Ok(selected.into_iter().next())
+            }
         }
-        std::thread::sleep(std::time::Duration::new(1, 0));
+        None => Ok(matches.value_of("instance_id").map(String::from)),
     }
 }
 
-#[cfg(unix)]
-fn user_only_perm(fp: &mut File) -> Result<(), Box<dyn std::error::Error>> {
-    let mut perm = fp.metadata()?.permissions();
-    perm.set_mode(0o600);
-    fp.set_permissions(perm)?;
-    Ok(())
+/// Switch for extending the instance while a long-running command is active
+fn auto_extend_arg() -> Arg<'static, 'static> {
+    Arg::with_name("auto_extend")
+        .long("auto-extend")
+        .help("request more time whenever the instance is about to expire")
 }
 
-#[cfg(not(unix))]
-fn user_only_perm(fp: &mut File) -> Result<(), Box<dyn std::error::Error>> {
-    Ok(())
+/// Call `f`, and if `--auto-extend` is given, then extend the instance while
+/// it is running.
+fn with_auto_extend<T, F: FnOnce() -> T>(
+    matches: &clap::ArgMatches,
+    ctx: &Context,
+    instance_id: &str,
+    f: F,
+) -> T {
+    if !matches.is_present("auto_extend") {
+        return f();
+    }
+    let on_result = |result: Result<u64, String>| match result {
+        Ok(expiration) => info!(
+            "extended instance {instance_id} until {}",
+            format_timestamp(expiration)
+        ),
+        Err(err) => eprintln!("warning: cannot extend instance {instance_id}: {err}"),
+    };
+    crate::with_auto_extend(
+        &ctx.client,
+        instance_id,
+        &crate::AutoExtend::default(),
+        on_result,
+        f,
+    )
 }
 
-fn write_secret_key(fname: &str, secret_key: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let mut fp = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .open(fname)?;
-    user_only_perm(&mut fp)?;
-    fp.write_all(secret_key.as_bytes())?;
-    fp.sync_all()?;
-    Ok(())
+/// Switch for using the built-in SSH client
+#[cfg(feature = "native-ssh")]
+fn native_arg() -> Arg<'static, 'static> {
+    Arg::with_name("native")
+        .long("native")
+        .help("use the built-in SSH client; it is also used if the ssh program is not found")
 }
 
-fn decide_default_confirmation(matches: &clap::ArgMatches) -> DefaultConfirmAnswer {
-    if matches.is_present("assume_no") {
-        DefaultConfirmAnswer::No
-    } else if matches.is_present("assume_yes") {
-        DefaultConfirmAnswer::Yes
-    } else {
-        DefaultConfirmAnswer::None
+/// Whether the built-in SSH client should be used instead of the ssh program
+#[cfg(feature = "native-ssh")]
+fn use_native_ssh(matches: &clap::ArgMatches) -> bool {
+    if matches.is_present("native") {
+        return true;
+    }
+    let name = if cfg!(windows) { "ssh.exe" } else { "ssh" };
+    match std::env::var_os("PATH") {
+        Some(paths) => !std::env::split_paths(&paths).any(|dir| dir.join(name).is_file()),
+        None => true,
     }
 }
 
-fn launch_subcommand(
+/// Run shell or command on instance with the built-in SSH client.
+///
+/// The exit code of the shell or command becomes the exit code of `rerobots`.
+#[cfg(feature = "native-ssh")]
+fn run_native_ssh(
     matches: &clap::ArgMatches,
-    api_token: Option<String>,
+    ctx: &Context,
+    instance: &serde_json::Value,
+    secret_key_path: Option<&str>,
+    mode: crate::native_ssh::Mode,
 ) -> Result<(), CliError> {
-    let wdid_or_wtype = matches
-        .value_of("wdid_or_wtype")
-        .ok_or("ID or type argument is required")?;
-
-    let public_key = match matches.value_of("public_key") {
-        Some(fname) => {
-            if !std::path::Path::new(fname).exists() {
-                return CliError::new(format!("Error: {fname} does not exist"), 1);
-            }
-            match std::fs::read_to_string(fname) {
-                Ok(s) => Some(s.trim().to_string()),
-                Err(err) => return CliError::new_stdio(err, 1),
-            }
-        }
-        None => None,
-    };
+    let secret_key_path = secret_key_path.ok_or(
+        "secret key is required by the built-in SSH client; get it with `rerobots get-ssh-key`",
+    )?;
+    let session =
+        crate::native_ssh::connect_instance(instance, std::path::Path::new(secret_key_path))
+            .map_err(|err| CliError::from(err.to_string()))?;
+    let instance_id = instance["id"].as_str().ok_or("id should be string")?;
+    let result = with_auto_extend(matches, ctx, instance_id, || session.run(mode));
+    if let Err(err) = session.close() {
+        debug!("error while disconnecting: {err}");
+    }
+    match result {
+        Ok(0) => Ok(()),
+        Ok(code) => CliError::newrc(code as i32),
+        Err(err) => CliError::new_std(err, 1),
+    }
+}
 
-    let payload = match client::api_launch_instance(wdid_or_wtype, api_token, public_key) {
-        Ok(p) => p,
-        Err(err) => return CliError::new_std(err, 1),
-    };
-    println!(
-        "{}",
-        payload["id"]
-            .as_str()
-            .expect("New instance ID should be string")
-    );
-    Ok(())
+/// ID of instance, which must be READY
+///
+/// If `instance_id` is None, then the only active instance is used.
+fn ready_instance(ctx: &Context, instance_id: Option<&str>) -> Result<String, CliError> {
+    let payload = ctx
+        .client
+        .instance_info(instance_id)
+        .map_err(|err| CliError::from(err.to_string()))?;
+    if payload["status"] != "READY" {
+        return Err(CliError::from("Error: instance is not READY"));
+    }
+    Ok(payload["id"]
+        .as_str()
+        .ok_or("id should be string")?
+        .to_string())
 }
 
-fn login_subcommand() -> Result<(), CliError> {
-    Ok(())
+/// Enable add-on of instance, if needed, and wait until it is active.
+///
+/// A message is printed to stderr if waiting is needed.
+fn activate_addon(
+    ctx: &Context,
+    addon: &str,
+    instance_id: &str,
+) -> Result<serde_json::Value, CliError> {
+    let mut waiting = false;
+    crate::enable_addon_and_wait(
+        &ctx.client,
+        addon,
+        instance_id,
+        Some(std::time::Duration::from_secs(60)),
+        |status| {
+            if status["status"] != "active" && !waiting {
+                eprintln!("Waiting for {addon} add-on of instance {instance_id} to be active");
+                waiting = true;
+            }
+        },
+    )
+    .map_err(|err| CliError::from(err.to_string()))
 }
 
-fn ssh_subcommand(matches: &clap::ArgMatches, api_token: Option<String>) -> Result<(), CliError> {
-    let secret_key_path = "key.pem";
-    let instance_id = matches.value_of("instance_id");
-    let payload = match client::api_instance_info(instance_id, api_token) {
-        Ok(p) => p,
-        Err(err) => return CliError::new_std(err, 1),
-    };
-    let status = payload["status"]
-        .as_str()
-        .expect("Instance status should be string");
-    if status != "READY" {
-        return CliError::new("Error: instance is not READY", 1);
-    }
-    let username = "root";
-    let fwd = payload["fwd"]
-        .as_object()
-        .expect("In response, fwd should be JSON object");
-    let ipv4 = fwd["ipv4"].as_str().expect("ipv4 should be string");
-    let port = fwd["port"].as_u64().expect("port should be integer");
-    let args: Vec<&str> = match matches.values_of("ssh_args") {
-        Some(v) => v.collect(),
-        None => vec![],
-    };
+/// Count of files and bytes, printed to stderr as files are packed or unpacked
+///
+/// If stderr is a terminal, the count is updated in place; otherwise, only the
+/// summary from [`FileProgress::finish()`] is printed.
+struct FileProgress {
+    verb: &'static str,
+    files: u64,
+    bytes: u64,
+    live: bool,
+}
 
-    let mut cmd = &mut std::process::Command::new("ssh");
-    let mut gave_secretkey = false;
-    for arg in args.iter() {
-        if arg == &"-i" {
-            gave_secretkey = true;
+impl FileProgress {
+    fn new(verb: &'static str) -> FileProgress {
+        use std::io::IsTerminal;
+        FileProgress {
+            verb,
+            files: 0,
+            bytes: 0,
+            live: std::io::stderr().is_terminal(),
         }
-        cmd = cmd.arg(arg);
-    }
-    if !gave_secretkey && std::path::Path::new(secret_key_path).exists() {
-        cmd = cmd.arg("-i").arg(secret_key_path);
     }
 
-    let status = match cmd
-        .arg("-p")
-        .arg(port.to_string())
-        .arg(format!("{username}@{ipv4}"))
-        .stdin(std::process::Stdio::inherit())
-        .stdout(std::process::Stdio::inherit())
-        .status()
-    {
-        Ok(rc) => rc,
-        Err(err) => return CliError::new_stdio(err, 1),
-    };
-    if status.success() {
-        Ok(())
-    } else {
-        CliError::newrc(1)
+    fn summary(&self) -> String {
+        format!(
+            "{} {} {} ({} bytes)",
+            self.verb,
+            self.files,
+            if self.files == 1 { "file" } else { "files" },
+            self.bytes
+        )
     }
-}
 
-fn token_info_subcommand(
-    matches: &clap::ArgMatches,
-    api_token: Option<String>,
-) -> Result<(), CliError> {
-    let api_token = match matches.value_of("token_file") {
-        Some(fname) => {
-            if !std::path::Path::new(fname).exists() {
-                return CliError::new(format!("Error: {fname} does not exist"), 1);
-            }
-            match std::fs::read_to_string(fname) {
-                Ok(s) => Some(s.trim().to_string()),
-                Err(err) => return CliError::new_stdio(err, 1),
-            }
+    fn update(&mut self, size: u64) {
+        self.files += 1;
+        self.bytes += size;
+        if self.live {
+            eprint!("\r{}", self.summary());
         }
-        None => match api_token {
-            Some(tok) => Some(tok),
-            None => std::env::var_os("REROBOTS_API_TOKEN").map(|tok| {
-                tok.into_string()
-                    .expect("REROBOTS_API_TOKEN variable should be valid string")
-            }),
-        },
-    };
-    let api_token = match api_token {
-        Some(a) => a,
-        None => {
-            return CliError::new("No API token given", 1);
+    }
+
+    fn finish(&self, detail: &str) {
+        if self.live {
+            eprint!("\r");
         }
-    };
+        eprintln!("{} {}", self.summary(), detail);
+    }
+}
 
-    let tc = match TokenClaims::new(&api_token) {
-        Ok(x) => x,
-        Err(err) => return CliError::new(err, 1),
-    };
-    println!("{tc}");
-    if tc.is_expired() {
-        println!("warning: This token is expired.");
-        return CliError::newrc(1);
+/// Remaining time of instance, for display
+fn format_remaining(instance: &serde_json::Value) -> String {
+    if instance["status"] == "TERMINATED" {
+        return "-".into();
+    }
+    match crate::remaining_time(instance) {
+        Some(r) if r.is_zero() => "expired".into(),
+        Some(r) => duration::format(r),
+        None => "-".into(),
     }
-    Ok(())
 }
 
 pub fn main() -> Result<(), CliError> {
-    let app = clap::App::new("rerobots API command-line client").max_term_width(80)
-        .bin_name("rerobots")
-        .subcommand(SubCommand::with_name("version")
-                    .about("Prints version number and exits"))
-        .arg(Arg::with_name("version")
-             .short("V")
-             .long("version")
-             .help("Prints version number and exits"))
-        .arg(Arg::with_name("verbose")
-             .short("v")
-             .long("verbose")
-             .help("Increases verboseness level of logs; ignored if RUST_LOG is defined"))
-        .arg(Arg::with_name("printformat")
-             .long("format")
-             .value_name("FORMAT")
-             .help("output formatting; options: YAML , JSON"))
-        .arg(Arg::with_name("apitoken")
-             .short("-t")
-             .value_name("FILE")
-             .help("plaintext file containing API token; with this flag, the REROBOTS_API_TOKEN environment variable is ignored"))
-        .arg(Arg::with_name("assume_yes")
-             .short("y")
-             .help("assume \"yes\" for any questions required to execute the command; otherwise, interactive prompts will appear to confirm actions as needed"))
-        .arg(Arg::with_name("assume_no")
-             .short("n")
-             .help("assume \"no\" for any questions required to execute the command; this can prevent destructive actions, e.g., overwriting a local file"))
-        .subcommand(SubCommand::with_name("search")
-                    .about("Search for matching deployments. empty query implies show all existing workspace deployments")
-                    .arg(Arg::with_name("query")
-                         .value_name("QUERY"))
-                    .arg(Arg::with_name("with_user_provided")
-                         .long("include-user-provided")
-                         .help("include user_provided workspace deployments in search")))
-        .subcommand(SubCommand::with_name("list")
-                    .about("List all instances by this user")
-                    .arg(Arg::with_name("quiet")
-                         .short("q")
-                         .long("quiet")
-                         .help("Only display instance IDs"))
-                    .arg(Arg::with_name("include_terminated")
-                         .long("include-terminated")
-                         .help("Include instances that are TERMINATED")))
-        .subcommand(SubCommand::with_name("info")
-                    .about("Print summary about instance")
-                    .arg(Arg::with_name("instance_id")
-                         .value_name("ID")))
-        .subcommand(SubCommand::with_name("get-ssh-key")
-                    .about("Get secret key for SSH access to instance")
-                    .arg(Arg::with_name("instance_id")
-                         .value_name("ID"))
-                    .arg(Arg::with_name("secret_key_path")
-                         .short("f")
-                         .value_name("FILE")
-                         .help("name of file in which to write new secret key (default key.pem)")))
-        .subcommand(SubCommand::with_name("wdinfo")
-                    .about("Print summary about workspace deployment")
-                    .arg(Arg::with_name("wdeployment_id")
-                         .value_name("ID")
-                         .required(true)))
-        .subcommand(SubCommand::with_name("launch")
-                    .about("Launch instance from specified workspace deployment or type")
-                    .arg(Arg::with_name("wdid_or_wtype")
-                         .value_name("ID")
-                         .required(true)
-                         .help("workspace type or deployment ID"))
-                    .arg(Arg::with_name("public_key")
-                         .long("public-key")
-                         .value_name("FILE")
-                         .help("path of public key to use; if not given, then a new key pair will be generated")))
-        .subcommand(SubCommand::with_name("login")
-                    .about("Login to rerobots.net"))
-        .subcommand(SubCommand::with_name("terminate")
-                    .about("Terminate instance")
-                    .arg(Arg::with_name("instance_id")
-                         .value_name("ID")))
-        .subcommand(SubCommand::with_name("isready")
-                    .about("Indicate whether instance is ready with exit code")
-                    .arg(Arg::with_name("instance_id")
-                         .value_name("ID"))
-                    .arg(Arg::with_name("blocking")
-                         .long("blocking")
-                         .help("Do not return until instance is non-INIT")))
-        .subcommand(SubCommand::with_name("ssh")
-                    .about("Connect to instance host via ssh")
-                    .arg(Arg::with_name("instance_id")
-                         .value_name("ID"))
-                    .arg(Arg::with_name("ssh_args")
-                         .required(false)
-                         .multiple(true)
-                         .last(true)))
-        .subcommand(SubCommand::with_name("token")
-                    .about("Get information about an API token")
-                    .arg(Arg::with_name("token_file")
-                         .value_name("FILE")
-                         .help("plaintext file containing API token; if not given, use REROBOTS_API_TOKEN environment variable or switch `-t`")));
+    let subcommands = subcommands();
+
+    let mut app = global_args(
+        clap::App::new("rerobots API command-line client")
+            .max_term_width(80)
+            .bin_name("rerobots"),
+    );
+    for subcommand in subcommands.iter() {
+        app = app.subcommand(subcommand.app());
+    }
 
     let matches = app.get_matches();
 
-    let default_loglevel = if matches.is_present("verbose") {
-        "info"
-    } else {
-        "warn"
-    };
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_loglevel))
-        .init();
+    if matches.is_present("version") {
+        println!(crate_version!());
+        return Ok(());
+    }
 
-    let pformat = match matches.value_of("printformat") {
-        Some(given_pformat) => {
-            let given_pformat_lower = given_pformat.to_lowercase();
-            if given_pformat_lower == "json" {
-                PrintingFormat::Json
-            } else if given_pformat_lower == "yaml" {
-                PrintingFormat::Yaml
-            } else {
-                return CliError::new(format!("unrecognized format: {given_pformat}").as_str(), 1);
-            }
-        }
-        None => PrintingFormat::Default,
+    let (profile_name, profile) = match load_profile(&matches) {
+        Ok(p) => p,
+        Err(err) => return CliError::new_std(err, 1),
     };
+    init_logging(&matches, &profile)?;
 
-    let api_token = match matches.value_of("apitoken") {
-        Some(fname) => {
-            if !std::path::Path::new(fname).exists() {
-                return CliError::new(format!("Error: {fname} does not exist"), 1);
-            }
-            match std::fs::read_to_string(fname) {
-                Ok(s) => Some(s.trim().to_string()),
-                Err(err) => return CliError::new_stdio(err, 1),
-            }
-        }
-        None => None,
-    };
+    let (name, submatches) = matches.subcommand();
+    let default_matches = clap::ArgMatches::default();
+    let submatches = submatches.unwrap_or(&default_matches);
+    let subcommand = subcommands.iter().find(|s| s.name() == name);
+    let uses_api_token = subcommand.is_some_and(|s| s.uses_api_token(submatches));
 
-    let default_confirm = decide_default_confirmation(&matches);
+    let ctx = context_from_matches(&matches, profile_name, profile, uses_api_token)?;
 
-    if matches.is_present("version") || matches.subcommand_matches("version").is_some() {
-        println!(crate_version!());
-    } else if let Some(matches) = matches.subcommand_matches("search") {
-        return search_subcommand(matches, api_token);
-    } else if let Some(matches) = matches.subcommand_matches("list") {
-        return list_subcommand(matches, api_token);
-    } else if let Some(matches) = matches.subcommand_matches("info") {
-        return info_subcommand(matches, api_token, pformat);
-    } else if let Some(matches) = matches.subcommand_matches("get-ssh-key") {
-        return get_sshkey_subcommand(matches, api_token, default_confirm);
-    } else if let Some(matches) = matches.subcommand_matches("wdinfo") {
-        return wdinfo_subcommand(matches, api_token, pformat);
-    } else if let Some(matches) = matches.subcommand_matches("launch") {
-        return launch_subcommand(matches, api_token);
-    } else if matches.subcommand_matches("login").is_some() {
-        return login_subcommand();
-    } else if let Some(matches) = matches.subcommand_matches("terminate") {
-        return terminate_subcommand(matches, api_token);
-    } else if let Some(matches) = matches.subcommand_matches("isready") {
-        return isready_subcommand(matches, api_token);
-    } else if let Some(matches) = matches.subcommand_matches("ssh") {
-        return ssh_subcommand(matches, api_token);
-    } else if let Some(matches) = matches.subcommand_matches("token") {
-        return token_info_subcommand(matches, api_token);
-    } else {
-        println!("No command given. Try `rerobots -h`");
+    match subcommand {
+        Some(subcommand) => {
+            if subcommand.checks_api_token(submatches) {
+                check_token(&ctx)?;
+            }
+            subcommand.run(submatches, &ctx)
+        }
+        None => {
+            println!("No command given. Try `rerobots -h`");
+            Ok(())
+        }
     }
-
-    Ok(())
 }
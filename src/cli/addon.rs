@@ -0,0 +1,179 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{AppSettings, Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::{render, PrintingFormat};
+
+pub struct Addon;
+
+impl super::Subcommand for Addon {
+    fn name(&self) -> &'static str {
+        "addon"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("List, enable, and disable add-ons of instance")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .arg(
+                Arg::with_name("instance_id")
+                    .value_name("ID")
+                    .required(true),
+            )
+            .subcommand(
+                SubCommand::with_name("list")
+                    .about("List add-ons supported by the workspace deployment, and their status"),
+            )
+            .subcommand(
+                SubCommand::with_name("enable")
+                    .about("Enable add-on, wait until it is active, and print connection details")
+                    .arg(addon_arg()),
+            )
+            .subcommand(
+                SubCommand::with_name("disable")
+                    .about("Disable add-on")
+                    .arg(addon_arg()),
+            )
+            .subcommand(
+                SubCommand::with_name("status")
+                    .about("Print status and connection details of add-on")
+                    .arg(addon_arg()),
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let instance_id = matches
+            .value_of("instance_id")
+            .ok_or("ID argument is required")?;
+        match matches.subcommand() {
+            ("list", _) => {
+                let supported = supported_addons(ctx, instance_id)?;
+                let mut statuses = serde_json::Map::new();
+                for addon in supported.iter() {
+                    let status = match ctx.client.addon_status(addon, instance_id) {
+                        Ok(Some(s)) => s,
+                        Ok(None) => serde_json::Value::Null,
+                        Err(err) => return CliError::new_std(err, 1),
+                    };
+                    statuses.insert(addon.clone(), status);
+                }
+                match ctx.pformat {
+                    PrintingFormat::Default => {
+                        println!("add-on\t\tstatus");
+                        for (addon, status) in statuses.iter() {
+                            let status = status["status"].as_str().unwrap_or("(not enabled)");
+                            println!("{addon}\t\t{status}");
+                        }
+                    }
+                    pformat => println!("{}", render(&statuses.into(), pformat)),
+                }
+                Ok(())
+            }
+            ("enable", Some(submatches)) => {
+                let addon = submatches.value_of("addon").ok_or("ADDON is required")?;
+                let supported = supported_addons(ctx, instance_id)?;
+                if !supported.iter().any(|a| a == addon) {
+                    return CliError::new(
+                        format!(
+                            "add-on {addon} is not supported by this workspace deployment; supported: {}",
+                            supported.join(", ")
+                        ),
+                        1,
+                    );
+                }
+                let status = super::activate_addon(ctx, addon, instance_id)?;
+                print_status(&status, ctx.pformat);
+                Ok(())
+            }
+            ("disable", Some(submatches)) => {
+                let addon = submatches.value_of("addon").ok_or("ADDON is required")?;
+                match ctx.client.disable_addon(addon, instance_id) {
+                    Ok(()) => Ok(()),
+                    Err(err) => CliError::new_std(err, 1),
+                }
+            }
+            ("status", Some(submatches)) => {
+                let addon = submatches.value_of("addon").ok_or("ADDON is required")?;
+                match ctx.client.addon_status(addon, instance_id) {
+                    Ok(Some(status)) => {
+                        print_status(&status, ctx.pformat);
+                        Ok(())
+                    }
+                    Ok(None) => CliError::new(format!("add-on {addon} is not enabled"), 1),
+                    Err(err) => CliError::new_std(err, 1),
+                }
+            }
+            _ => CliError::new("unknown subcommand", 1),
+        }
+    }
+}
+
+fn addon_arg() -> Arg<'static, 'static> {
+    Arg::with_name("addon")
+        .value_name("ADDON")
+        .required(true)
+        .help("name of add-on, e.g., cam")
+}
+
+/// Add-ons that the workspace deployment of the instance supports
+fn supported_addons(ctx: &Context, instance_id: &str) -> Result<Vec<String>, CliError> {
+    let instance = ctx
+        .client
+        .instance_info(Some(instance_id))
+        .map_err(|err| CliError::from(err.to_string()))?;
+    let wdeployment_id = instance["deployment"]
+        .as_str()
+        .ok_or("deployment should be string")?;
+    let wdeployment = ctx
+        .client
+        .wdeployment_info(wdeployment_id)
+        .map_err(|err| CliError::from(err.to_string()))?;
+    Ok(match wdeployment["supported_addons"].as_array() {
+        Some(addons) => addons
+            .iter()
+            .filter_map(|a| a.as_str())
+            .map(String::from)
+            .collect(),
+        None => vec![],
+    })
+}
+
+/// Print status and connection details of add-on, which differ among add-ons
+///
+/// Each element of a list is printed on its own line, e.g., `url: ...`
+fn print_status(status: &serde_json::Value, pformat: PrintingFormat) {
+    if pformat != PrintingFormat::Default {
+        println!("{}", render(status, pformat));
+        return;
+    }
+    match status.as_object() {
+        Some(details) => {
+            for (key, value) in details.iter() {
+                let values = match value.as_array() {
+                    Some(a) => a.iter().collect(),
+                    None => vec![value],
+                };
+                for value in values {
+                    match value.as_str() {
+                        Some(s) => println!("{key}: {s}"),
+                        None => println!("{key}: {value}"),
+                    }
+                }
+            }
+        }
+        None => println!("{status}"),
+    }
+}
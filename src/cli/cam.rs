@@ -0,0 +1,150 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::{Path, PathBuf};
+
+use clap::{Arg, ArgGroup, SubCommand};
+
+use super::{CliError, Context};
+use crate::{duration, Client};
+
+pub struct Cam;
+
+impl super::Subcommand for Cam {
+    fn name(&self) -> &'static str {
+        "cam"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Get images from cameras of instance")
+            .arg(
+                Arg::with_name("instance_id")
+                    .value_name("ID")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("camera_id")
+                    .value_name("CAMERA")
+                    .default_value("0")
+                    .help("camera ID, as listed by `wdinfo`"),
+            )
+            .arg(
+                Arg::with_name("snapshot")
+                    .long("snapshot")
+                    .value_name("FILE")
+                    .help("save one image to FILE"),
+            )
+            .arg(
+                Arg::with_name("timelapse")
+                    .long("timelapse")
+                    .requires("out")
+                    .help("save images repeatedly to the directory given by --out"),
+            )
+            .group(
+                ArgGroup::with_name("mode")
+                    .args(&["snapshot", "timelapse"])
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("interval")
+                    .long("interval")
+                    .value_name("DURATION")
+                    .requires("timelapse")
+                    .help("time between images, e.g., 10s or 1m; default is 2s"),
+            )
+            .arg(
+                Arg::with_name("count")
+                    .long("count")
+                    .value_name("N")
+                    .requires("timelapse")
+                    .help("number of images; if not given, then continue until interrupted"),
+            )
+            .arg(
+                Arg::with_name("out")
+                    .long("out")
+                    .value_name("DIR")
+                    .requires("timelapse")
+                    .help(
+                        "directory in which to save images; file names have the camera ID and time",
+                    ),
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let camera_id: u32 = match matches.value_of("camera_id").unwrap_or("0").parse() {
+            Ok(c) => c,
+            Err(_) => return CliError::new("CAMERA should be a nonnegative integer", 1),
+        };
+        let count = match matches.value_of("count").map(|c| c.parse::<u64>()) {
+            Some(Ok(0)) | Some(Err(_)) => {
+                return CliError::new("--count should be a positive integer", 1)
+            }
+            Some(Ok(c)) => Some(c),
+            None => None,
+        };
+        let interval = match duration::parse(matches.value_of("interval").unwrap_or("2s")) {
+            Ok(d) => d,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+
+        let instance_id = super::ready_instance(ctx, matches.value_of("instance_id"))?;
+        let instance_id = instance_id.as_str();
+        super::activate_addon(ctx, "cam", instance_id)?;
+
+        if let Some(path) = matches.value_of("snapshot") {
+            return save_image(&ctx.client, instance_id, camera_id, Path::new(path));
+        }
+
+        let out_dir = PathBuf::from(matches.value_of("out").ok_or("--out is required")?);
+        if let Err(err) = std::fs::create_dir_all(&out_dir) {
+            return CliError::new_stdio(err, 1);
+        }
+        let mut taken = 0;
+        loop {
+            let started = std::time::Instant::now();
+            let name = format!(
+                "cam{}-{}.jpg",
+                camera_id,
+                chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+            );
+            let path = out_dir.join(name);
+            save_image(&ctx.client, instance_id, camera_id, &path)?;
+            println!("{}", path.display());
+            taken += 1;
+            if count.is_some_and(|c| taken >= c) {
+                return Ok(());
+            }
+            if let Some(remaining) = interval.checked_sub(started.elapsed()) {
+                std::thread::sleep(remaining);
+            }
+        }
+    }
+}
+
+fn save_image(
+    client: &Client,
+    instance_id: &str,
+    camera_id: u32,
+    path: &Path,
+) -> Result<(), CliError> {
+    let image = match client.camera_image(instance_id, camera_id) {
+        Ok(i) => i,
+        Err(err) => return CliError::new_std(err, 1),
+    };
+    match std::fs::write(path, image) {
+        Ok(()) => Ok(()),
+        Err(err) => CliError::new(format!("{}: {}", path.display(), err), 1),
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::{duration, format_timestamp, render, PrintingFormat};
+
+pub struct Extend;
+
+impl super::Subcommand for Extend {
+    fn name(&self) -> &'static str {
+        "extend"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Request more time for instance")
+            .arg(Arg::with_name("instance_id").value_name("ID"))
+            .arg(super::selector_arg())
+            .arg(
+                Arg::with_name("by")
+                    .long("by")
+                    .value_name("DURATION")
+                    .default_value("1h")
+                    .help("how much time to add, e.g., 30m"),
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let by = match duration::parse(matches.value_of("by").unwrap_or("1h")) {
+            Ok(d) if !d.is_zero() => d,
+            Ok(_) => return CliError::new("duration must be positive", 1),
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        let instance_id = super::instance_id_from(matches, ctx)?;
+        let payload = match ctx.client.extend(instance_id.as_deref(), by) {
+            Ok(p) => p,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        match ctx.pformat {
+            PrintingFormat::Default => {
+                let expiration = payload["expiration"]
+                    .as_u64()
+                    .ok_or("expiration should be integer")?;
+                println!(
+                    "expires at {} (in {})",
+                    format_timestamp(expiration),
+                    super::format_remaining(&payload)
+                );
+            }
+            pformat => println!("{}", render(&payload, pformat)),
+        }
+        Ok(())
+    }
+}
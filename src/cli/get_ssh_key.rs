@@ -0,0 +1,180 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::sshkey::{create_keys_dir, instance_comment, SecretKey};
+use crate::{duration, write_secret_key};
+
+pub struct GetSshKey;
+
+impl super::Subcommand for GetSshKey {
+    fn name(&self) -> &'static str {
+        "get-ssh-key"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Get secret key for SSH access to instance")
+            .arg(Arg::with_name("instance_id").value_name("ID"))
+            .arg(
+                Arg::with_name("secret_key_path")
+                    .short("f")
+                    .value_name("FILE")
+                    .help("name of file in which to write new secret key, or - for stdout; if not given, then the key is kept in the rerobots keys directory, where `ssh` and `exec` find it"),
+            )
+            .arg(
+                Arg::with_name("format")
+                    .long("format")
+                    .value_name("FORMAT")
+                    .possible_values(&["openssh", "pem", "ppk"])
+                    .help("convert the key to FORMAT; ppk is for PuTTY (default: as received)"),
+            )
+            .arg(
+                Arg::with_name("fingerprint")
+                    .long("fingerprint")
+                    .conflicts_with_all(&["secret_key_path", "format", "agent"])
+                    .help("print SHA256 fingerprint and public key, in OpenSSH format, instead of the secret key"),
+            )
+            .arg(
+                Arg::with_name("agent")
+                    .long("agent")
+                    .conflicts_with("secret_key_path")
+                    .help("load the key into ssh-agent, found through SSH_AUTH_SOCK, until the instance expires, instead of writing it to a file"),
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let instance_id = match ctx.client.select_instance(matches.value_of("instance_id")) {
+            Ok(id) => id,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        if matches.is_present("agent") {
+            return load_into_agent(ctx, &instance_id);
+        }
+
+        if matches.is_present("fingerprint") {
+            return print_fingerprint(ctx, &instance_id);
+        }
+
+        let format = matches.value_of("format");
+        // If None, then write to stdout
+        let path = match matches.value_of("secret_key_path") {
+            Some("-") => None,
+            Some(path) => {
+                super::confirm_overwrite(ctx, path, "new secret key")?;
+                Some(path.to_string())
+            }
+            None => {
+                let dir = match create_keys_dir() {
+                    Ok(d) => d,
+                    Err(err) => return CliError::new_std(err, 1),
+                };
+                let extension = if format == Some("ppk") { "ppk" } else { "pem" };
+                Some(
+                    dir.join(format!("{instance_id}.{extension}"))
+                        .to_str()
+                        .ok_or("path should be valid string")?
+                        .to_string(),
+                )
+            }
+        };
+
+        let key = match ctx.client.instance_sshkey(Some(&instance_id)) {
+            Ok(k) => k,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        let key = match format {
+            Some(format) => match convert(&key, format, &instance_id) {
+                Ok(k) => k,
+                Err(err) => return CliError::new_std(err, 1),
+            },
+            None => key,
+        };
+
+        let path = match path {
+            Some(p) => p,
+            None => {
+                print!("{key}");
+                return Ok(());
+            }
+        };
+        match write_secret_key(&path, &key) {
+            Ok(()) => {
+                if matches.value_of("secret_key_path").is_none() {
+                    eprintln!("Wrote secret key to {path}");
+                }
+                Ok(())
+            }
+            Err(err) => CliError::new_std(err, 1),
+        }
+    }
+}
+
+fn convert(
+    key: &str,
+    format: &str,
+    instance_id: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let key = SecretKey::parse(key)?;
+    let comment = instance_comment(instance_id);
+    match format {
+        "openssh" => key.to_openssh(&comment),
+        "pem" => key.to_pem(),
+        "ppk" => key.to_ppk(&comment),
+        _ => Err(format!("unknown key format: {format}").into()),
+    }
+}
+
+fn print_fingerprint(ctx: &Context, instance_id: &str) -> Result<(), CliError> {
+    let result = ctx
+        .client
+        .instance_sshkey(Some(instance_id))
+        .and_then(|key| SecretKey::parse(&key))
+        .and_then(|key| {
+            Ok((
+                key.fingerprint()?,
+                key.public_openssh(&instance_comment(instance_id))?,
+            ))
+        });
+    match result {
+        Ok((fingerprint, public_key)) => {
+            println!("{fingerprint}\n{public_key}");
+            Ok(())
+        }
+        Err(err) => CliError::new_std(err, 1),
+    }
+}
+
+fn load_into_agent(ctx: &Context, instance_id: &str) -> Result<(), CliError> {
+    let result = ctx
+        .client
+        .instance_info(Some(instance_id))
+        .and_then(|instance| super::add_to_agent(ctx, &instance));
+    match result {
+        Ok(Some(lifetime)) => {
+            eprintln!(
+                "Added secret key of instance {instance_id} to ssh-agent for {}",
+                duration::format(lifetime)
+            );
+            Ok(())
+        }
+        Ok(None) => {
+            eprintln!("Added secret key of instance {instance_id} to ssh-agent");
+            Ok(())
+        }
+        Err(err) => CliError::new_std(err, 1),
+    }
+}
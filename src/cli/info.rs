@@ -0,0 +1,64 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::render;
+use crate::state::State;
+
+pub struct Info;
+
+impl super::Subcommand for Info {
+    fn name(&self) -> &'static str {
+        "info"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Print summary about instance")
+            .arg(Arg::with_name("instance_id").value_name("ID"))
+            .arg(super::selector_arg())
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let instance_id = super::instance_id_from(matches, ctx)?;
+        let mut payload = match ctx.client.instance_info(instance_id.as_deref()) {
+            Ok(p) => p,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        payload["url"] = format!(
+            "https://rerobots.net/instance/{}",
+            payload["id"].as_str().expect("id should be string")
+        )
+        .into();
+        let mut labels = match payload["labels"].as_object() {
+            Some(server_labels) => server_labels.clone(),
+            None => serde_json::Map::new(),
+        };
+        if let (Some(id), Ok(local)) = (payload["id"].as_str(), State::load()) {
+            for (k, v) in local.labels(id) {
+                labels.insert(k, v.into());
+            }
+        }
+        if !labels.is_empty() {
+            payload["labels"] = labels.into();
+        }
+        if !payload["expiration"].is_null() {
+            payload["remaining"] = super::format_remaining(&payload).into();
+        }
+        println!("{}", render(&payload, ctx.pformat));
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::wait_until_ready;
+
+pub struct Isready;
+
+impl super::Subcommand for Isready {
+    fn name(&self) -> &'static str {
+        "isready"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Indicate whether instance is ready with exit code")
+            .arg(Arg::with_name("instance_id").value_name("ID"))
+            .arg(
+                Arg::with_name("blocking")
+                    .long("blocking")
+                    .help("Do not return until instance is non-INIT"),
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let blocking = matches.is_present("blocking");
+        let instance_id = matches.value_of("instance_id");
+        let payload = if blocking {
+            wait_until_ready(&ctx.client, instance_id, None)
+        } else {
+            ctx.client.instance_info(instance_id)
+        };
+        let payload = match payload {
+            Ok(p) => p,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        let status = payload["status"]
+            .as_str()
+            .expect("Instance status should be string");
+        if status == "READY" {
+            Ok(())
+        } else {
+            CliError::newrc(1)
+        }
+    }
+}
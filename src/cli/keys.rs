@@ -0,0 +1,191 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use clap::{AppSettings, Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::sshkey::{instance_keys, SecretKey};
+use crate::{render, write_secret_key, PrintingFormat};
+
+pub struct Keys;
+
+impl super::Subcommand for Keys {
+    fn name(&self) -> &'static str {
+        "keys"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Manage SSH keys, including secret keys of instances")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("generate")
+                        .about("Generate new key pair, e.g., for `launch --public-key`")
+                        .arg(Arg::with_name("secret_key_path")
+                             .value_name("FILE")
+                             .required(true)
+                             .help("file in which to write the secret key; the public key is written to FILE.pub"))
+                        .arg(Arg::with_name("key_type")
+                             .long("type")
+                             .value_name("TYPE")
+                             .possible_values(&["ed25519", "rsa"])
+                             .help("type of key (default ed25519)"))
+                        .arg(Arg::with_name("bits")
+                             .long("bits")
+                             .value_name("N")
+                             .help("size of RSA key in bits (default 3072)")))
+            .subcommand(SubCommand::with_name("list")
+                        .about("List secret keys in the rerobots keys directory and their instances"))
+            .subcommand(SubCommand::with_name("prune")
+                        .about("Delete secret keys of instances that are terminated; keys of instances that are not found are kept"))
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        match matches.subcommand() {
+            ("generate", Some(submatches)) => generate(submatches, ctx),
+            ("list", _) => list(ctx),
+            ("prune", _) => prune(ctx),
+            _ => CliError::new("unknown subcommand", 1),
+        }
+    }
+
+    /// Only `prune` calls the API.
+    fn uses_api_token(&self, matches: &clap::ArgMatches) -> bool {
+        matches.subcommand_name() == Some("prune")
+    }
+}
+
+fn generate(matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+    let path = matches
+        .value_of("secret_key_path")
+        .ok_or("FILE argument is required")?;
+    let key = match matches.value_of("key_type") {
+        Some("rsa") => {
+            let bits = match matches.value_of("bits").unwrap_or("3072").parse() {
+                Ok(b) => b,
+                Err(_) => return CliError::new("Error: --bits should be a positive integer", 1),
+            };
+            SecretKey::generate_rsa(bits)
+        }
+        _ => SecretKey::generate_ed25519(),
+    };
+    let key = match key {
+        Ok(k) => k,
+        Err(err) => return CliError::new_std(err, 1),
+    };
+
+    let public_path = format!("{path}.pub");
+    super::confirm_overwrite(ctx, path, "new secret key")?;
+    super::confirm_overwrite(ctx, &public_path, "new public key")?;
+    let comment = "rerobots";
+    let result = key
+        .to_openssh(comment)
+        .and_then(|secret| write_secret_key(path, &secret))
+        .and_then(|()| key.public_openssh(comment))
+        .and_then(|public| Ok(std::fs::write(&public_path, public + "\n")?));
+    if let Err(err) = result {
+        return CliError::new_std(err, 1);
+    }
+    eprintln!("Wrote secret key to {path} and public key to {public_path}");
+    match key.fingerprint() {
+        Ok(fingerprint) => {
+            println!("{fingerprint}");
+            Ok(())
+        }
+        Err(err) => CliError::new_std(err, 1),
+    }
+}
+
+fn list(ctx: &Context) -> Result<(), CliError> {
+    let keys = match instance_keys() {
+        Ok(k) => k,
+        Err(err) => return CliError::new_std(err, 1),
+    };
+    // Instances that have the same key are shown together
+    let mut by_fingerprint: BTreeMap<String, (&'static str, Vec<String>)> = BTreeMap::new();
+    for (instance_id, path) in keys
+        .into_iter()
+        .flat_map(|(id, paths)| paths.into_iter().map(move |p| (id.clone(), p)))
+    {
+        let key = std::fs::read_to_string(&path)
+            .map_err(|err| err.into())
+            .and_then(|text| SecretKey::parse(&text));
+        let (fingerprint, algorithm) = match key.and_then(|k| Ok((k.fingerprint()?, k.algorithm())))
+        {
+            Ok(f) => f,
+            Err(err) => {
+                eprintln!("warning: cannot read {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        let instances = &mut by_fingerprint
+            .entry(fingerprint)
+            .or_insert((algorithm, vec![]))
+            .1;
+        // The key of an instance can be in more than one format
+        if !instances.contains(&instance_id) {
+            instances.push(instance_id);
+        }
+    }
+
+    match ctx.pformat {
+        PrintingFormat::Default => {
+            for (fingerprint, (algorithm, instances)) in by_fingerprint.iter() {
+                println!("{fingerprint}\t{algorithm}\t{}", instances.join(","));
+            }
+        }
+        pformat => {
+            let keys: Vec<serde_json::Value> = by_fingerprint
+                .into_iter()
+                .map(|(fingerprint, (algorithm, instances))| {
+                    serde_json::json!({
+                        "fingerprint": fingerprint,
+                        "type": algorithm,
+                        "instances": instances,
+                    })
+                })
+                .collect();
+            println!("{}", render(&keys.into(), pformat));
+        }
+    }
+    Ok(())
+}
+
+fn prune(ctx: &Context) -> Result<(), CliError> {
+    let keys = match instance_keys() {
+        Ok(k) => k,
+        Err(err) => return CliError::new_std(err, 1),
+    };
+    for (instance_id, paths) in keys {
+        // Only keys of instances that are known to be terminated are deleted.
+        // Instances might not be found, e.g., if they belong to another
+        // account, so their keys are kept.
+        match ctx.client.instance_info(Some(&instance_id)) {
+            Ok(payload) if payload["status"] == "TERMINATED" => {}
+            Ok(_) => continue,
+            Err(err) => {
+                eprintln!("warning: keeping key of instance {instance_id}: {err}");
+                continue;
+            }
+        }
+        for path in paths {
+            if let Err(err) = std::fs::remove_file(&path) {
+                return CliError::new(format!("{}: {}", path.display(), err), 1);
+            }
+            eprintln!("Deleted {}", path.display());
+        }
+    }
+    Ok(())
+}
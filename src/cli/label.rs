@@ -0,0 +1,90 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::render;
+use crate::state::{self, State};
+
+pub struct Label;
+
+impl super::Subcommand for Label {
+    fn name(&self) -> &'static str {
+        "label"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Show or change labels of instance")
+            .after_help("Labels are kept locally, keyed by instance ID, together with labels given to `launch`. Remove a label with KEY-, e.g., `rerobots label ID project-`")
+            .arg(Arg::with_name("instance_id")
+                 .value_name("ID")
+                 .required(true))
+            .arg(Arg::with_name("labels")
+                 .value_name("KEY=VALUE")
+                 .multiple(true)
+                 .help("labels to add or replace; if none, then print current labels"))
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let instance_id = matches
+            .value_of("instance_id")
+            .ok_or("ID argument is required")?;
+        let mut local = match State::load() {
+            Ok(s) => s,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+
+        let changes: Vec<&str> = match matches.values_of("labels") {
+            Some(v) => v.collect(),
+            None => {
+                let labels = local.labels(instance_id);
+                match ctx.pformat {
+                    crate::PrintingFormat::Default => {
+                        for (k, v) in labels.iter() {
+                            println!("{k}={v}");
+                        }
+                    }
+                    pformat => println!("{}", render(&serde_json::json!(labels), pformat)),
+                }
+                return Ok(());
+            }
+        };
+
+        let mut removals = vec![];
+        let mut additions = state::Labels::new();
+        for change in changes {
+            match change.strip_suffix('-') {
+                Some(key) if !change.contains('=') => removals.push(key),
+                _ => match crate::spec::parse_label(change) {
+                    Ok((k, v)) => {
+                        additions.insert(k, v);
+                    }
+                    Err(err) => return CliError::new_std(err, 1),
+                },
+            }
+        }
+        for key in removals {
+            if !local.remove_label(instance_id, key) {
+                return CliError::new(format!("instance has no label {key}"), 1);
+            }
+        }
+        local.add_labels(instance_id, &additions);
+        match local.save() {
+            Ok(()) => Ok(()),
+            Err(err) => CliError::new_std(err, 1),
+        }
+    }
+}
@@ -0,0 +1,259 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::spec::{parse_label, LaunchSpec, Repo};
+use crate::sshkey::create_keys_dir;
+use crate::state::{Labels, State};
+use crate::{duration, wait_for_reservation, write_secret_key};
+
+pub struct Launch;
+
+impl super::Subcommand for Launch {
+    fn name(&self) -> &'static str {
+        "launch"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Launch instance from specified workspace deployment or type")
+            .arg(Arg::with_name("wdid_or_wtype")
+                 .value_name("ID")
+                 .required_unless("spec")
+                 .help("workspace type or deployment ID"))
+            .arg(Arg::with_name("public_key")
+                 .long("public-key")
+                 .value_name("FILE")
+                 .help("path of public key to use; if not given, then a new key pair will be generated"))
+            .arg(Arg::with_name("queue")
+                 .long("queue")
+                 .help("reserve the workspace deployment instead of launching directly, and wait until the reservation is fulfilled"))
+            .arg(Arg::with_name("timeout")
+                 .long("timeout")
+                 .value_name("DURATION")
+                 .requires("queue")
+                 .help("with --queue, cancel the reservation if it is not fulfilled within DURATION, e.g., 30m"))
+            .arg(Arg::with_name("max_duration")
+                 .long("max-duration")
+                 .value_name("DURATION")
+                 .help("terminate the instance after DURATION, e.g., 2h"))
+            .arg(Arg::with_name("repo")
+                 .long("repo")
+                 .value_name("URL")
+                 .help("git repository to clone in the instance"))
+            .arg(Arg::with_name("branch")
+                 .long("branch")
+                 .value_name("NAME")
+                 .requires("repo")
+                 .help("branch, tag, or commit of the repository given by --repo"))
+            .arg(Arg::with_name("startup_script")
+                 .long("startup-script")
+                 .value_name("FILE")
+                 .help("script to run in the instance when it is ready"))
+            .arg(Arg::with_name("label")
+                 .short("l")
+                 .long("label")
+                 .value_name("KEY=VALUE")
+                 .multiple(true)
+                 .number_of_values(1)
+                 .help("label to attach to the instance; can be given more than once"))
+            .arg(Arg::with_name("spec")
+                 .long("spec")
+                 .value_name("FILE")
+                 .help("YAML file of launch options; options given as switches take precedence"))
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let spec = spec_from_matches(matches)?;
+        let options = match spec.request_body() {
+            Ok(o) => o,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        let wdid_or_wtype = spec
+            .workspace
+            .as_deref()
+            .ok_or("ID or type argument is required")?;
+
+        if matches.is_present("queue") {
+            return launch_from_queue(matches, ctx, wdid_or_wtype, &options, &spec);
+        }
+
+        let payload = match ctx.client.launch_with(wdid_or_wtype, &options) {
+            Ok(p) => p,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        let instance_id = payload["id"]
+            .as_str()
+            .expect("New instance ID should be string");
+        remember_labels(instance_id, &spec.labels);
+        remember_key(instance_id, payload["sshkey"].as_str());
+        println!("{instance_id}");
+        Ok(())
+    }
+}
+
+/// Keep labels in local state, so that the instance can be selected by them.
+fn remember_labels(instance_id: &str, labels: &Labels) {
+    if labels.is_empty() {
+        return;
+    }
+    let result = State::load().and_then(|mut local| {
+        local.add_labels(instance_id, labels);
+        local.save()
+    });
+    if let Err(err) = result {
+        eprintln!("warning: cannot save labels: {err}");
+    }
+}
+
+/// Keep the secret key that rerobots generated for the new instance in the
+/// rerobots keys directory, so that `ssh` finds it. Secret keys of public keys
+/// given with `--public-key` are not copied.
+fn remember_key(instance_id: &str, generated: Option<&str>) {
+    let secret = match generated {
+        Some(key) => key,
+        None => return,
+    };
+    let result = create_keys_dir().and_then(|dir| {
+        let path = dir.join(format!("{instance_id}.pem"));
+        write_secret_key(path.to_str().ok_or("path should be valid string")?, secret)
+    });
+    if let Err(err) = result {
+        eprintln!("warning: cannot save secret key: {err}");
+    }
+}
+
+fn spec_from_matches(matches: &clap::ArgMatches) -> Result<LaunchSpec, CliError> {
+    let base = match matches.value_of("spec") {
+        Some(path) => match LaunchSpec::from_file(std::path::Path::new(path)) {
+            Ok(s) => s,
+            Err(err) => return Err(CliError::from(err.to_string())),
+        },
+        None => LaunchSpec::default(),
+    };
+
+    let mut labels = std::collections::BTreeMap::new();
+    if let Some(given) = matches.values_of("label") {
+        for label in given {
+            match parse_label(label) {
+                Ok((k, v)) => {
+                    labels.insert(k, v);
+                }
+                Err(err) => return Err(CliError::from(err.to_string())),
+            }
+        }
+    }
+    let flags = LaunchSpec {
+        workspace: matches.value_of("wdid_or_wtype").map(String::from),
+        public_key: matches.value_of("public_key").map(Into::into),
+        max_duration: matches.value_of("max_duration").map(String::from),
+        repo: matches.value_of("repo").map(|url| Repo {
+            url: url.to_string(),
+            branch: matches.value_of("branch").map(String::from),
+        }),
+        startup_script: matches.value_of("startup_script").map(Into::into),
+        labels,
+    };
+    Ok(base.merge(flags))
+}
+
+pub(super) fn read_public_key(matches: &clap::ArgMatches) -> Result<Option<String>, CliError> {
+    match matches.value_of("public_key") {
+        Some(fname) => {
+            if !std::path::Path::new(fname).exists() {
+                return Err(CliError::from(format!("Error: {fname} does not exist")));
+            }
+            match std::fs::read_to_string(fname) {
+                Ok(s) => Ok(Some(s.trim().to_string())),
+                Err(err) => Err(CliError::from(format!("{err}"))),
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+fn launch_from_queue(
+    matches: &clap::ArgMatches,
+    ctx: &Context,
+    wdid_or_wtype: &str,
+    options: &serde_json::Value,
+    spec: &LaunchSpec,
+) -> Result<(), CliError> {
+    let timeout = match matches.value_of("timeout") {
+        Some(given) => match duration::parse(given) {
+            Ok(d) => Some(d),
+            Err(err) => return CliError::new_std(err, 1),
+        },
+        None => None,
+    };
+
+    let payload = match ctx.client.reserve_with(wdid_or_wtype, options) {
+        Ok(p) => p,
+        Err(err) => return CliError::new_std(err, 1),
+    };
+    let reservation_id = payload["id"]
+        .as_str()
+        .ok_or("Reservation ID should be string")?;
+    eprintln!("Created reservation {reservation_id}");
+
+    // If interrupted while waiting, then cancel the reservation before exiting.
+    #[cfg(unix)]
+    let guard = crate::signals::Guard::new();
+    let mut last_position = None;
+    let result = wait_for_reservation(
+        &ctx.client,
+        reservation_id,
+        timeout,
+        std::time::Duration::from_secs(1),
+        |details| {
+            let position = details["position"].as_u64();
+            if details["status"] == "PENDING" && position != last_position {
+                if let Some(p) = position {
+                    eprintln!("Waiting for reservation {reservation_id}: position {p} in queue");
+                }
+                last_position = position;
+            }
+            #[cfg(unix)]
+            if guard.received().is_some() {
+                return false;
+            }
+            true
+        },
+    );
+    #[cfg(unix)]
+    drop(guard);
+    match result {
+        Ok(instance_id) => {
+            remember_labels(&instance_id, &spec.labels);
+            // Unlike the response to launch, the reservation does not include
+            // the key that was generated for the instance.
+            if spec.public_key.is_none() {
+                match ctx.client.instance_sshkey(Some(&instance_id)) {
+                    Ok(key) => remember_key(&instance_id, Some(&key)),
+                    Err(err) => eprintln!("warning: cannot get secret key: {err}"),
+                }
+            }
+            println!("{instance_id}");
+            Ok(())
+        }
+        Err(err) => {
+            if ctx.client.cancel_reservation(reservation_id).is_ok() {
+                eprintln!("Canceled reservation {reservation_id}");
+            }
+            CliError::new_std(err, 1)
+        }
+    }
+}
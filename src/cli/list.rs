@@ -0,0 +1,110 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::instances_info;
+use crate::state::{self, State};
+
+pub struct List;
+
+impl super::Subcommand for List {
+    fn name(&self) -> &'static str {
+        "list"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("List all instances by this user")
+            .arg(
+                Arg::with_name("quiet")
+                    .short("q")
+                    .long("quiet")
+                    .help("Only display instance IDs"),
+            )
+            .arg(
+                Arg::with_name("include_terminated")
+                    .long("include-terminated")
+                    .help("Include instances that are TERMINATED"),
+            )
+            .arg(
+                super::selector_arg()
+                    .help("Only list instances that have the label; can be given more than once"),
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let be_quiet = matches.is_present("quiet");
+        let include_terminated = matches.is_present("include_terminated");
+        let payload = match ctx.client.instances(include_terminated) {
+            Ok(p) => p,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        let selector = match matches.values_of("selector") {
+            Some(given) => match state::parse_selector(&given.collect::<Vec<&str>>()) {
+                Ok(s) => s,
+                Err(err) => return CliError::new_std(err, 1),
+            },
+            None => state::Labels::new(),
+        };
+        let local = if selector.is_empty() {
+            State::default()
+        } else {
+            match State::load() {
+                Ok(s) => s,
+                Err(err) => return CliError::new_std(err, 1),
+            }
+        };
+        let instances: Vec<&str> = payload["workspace_instances"]
+            .as_array()
+            .expect("workspace_instances should be array")
+            .iter()
+            .map(|inst| {
+                inst.as_str()
+                    .expect("Elements of workspace_instances should be strings")
+            })
+            .collect();
+        let selected = super::with_labels(ctx, &instances, &local, &selector);
+        let mut rows = vec![];
+        for (j, inst) in instances.iter().copied().enumerate() {
+            if selected.contains(&inst) {
+                let wdeployment_id = payload["workspace_deployments"]
+                    .as_array()
+                    .expect("workspace_deployments should be array")[j]
+                    .as_str()
+                    .expect("Elements of workspace_deployments should be strings");
+                rows.push((inst, wdeployment_id));
+            }
+        }
+
+        if be_quiet {
+            for (inst, _) in rows {
+                println!("{inst}");
+            }
+            return Ok(());
+        }
+        let instance_ids: Vec<&str> = rows.iter().map(|(inst, _)| *inst).collect();
+        let details = instances_info(&ctx.client, &instance_ids);
+        println!("instance\t\t\t\tworkspace deployment\t\t\tremaining");
+        for ((inst, wdeployment_id), details) in rows.iter().zip(details) {
+            let remaining = match details {
+                Ok(d) => super::format_remaining(&d),
+                Err(_) => "(unknown)".into(),
+            };
+            println!("{inst}\t{wdeployment_id}\t{remaining}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::SubCommand;
+
+use super::{CliError, Context};
+
+pub struct Login;
+
+impl super::Subcommand for Login {
+    fn name(&self) -> &'static str {
+        "login"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name()).about("Login to rerobots.net")
+    }
+
+    fn uses_api_token(&self, _matches: &clap::ArgMatches) -> bool {
+        false
+    }
+
+    fn run(&self, _matches: &clap::ArgMatches, _ctx: &Context) -> Result<(), CliError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::relay::{self, Upstream};
+
+pub struct Mistyproxy;
+
+impl super::Subcommand for Mistyproxy {
+    fn name(&self) -> &'static str {
+        "mistyproxy"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Activate the mistyproxy add-on of instance, and print the proxy URLs")
+            .arg(Arg::with_name("instance_id")
+                 .value_name("ID")
+                 .required(true))
+            .arg(Arg::with_name("listen")
+                 .long("listen")
+                 .value_name("ADDRESS")
+                 .help("Relay HTTP and WebSocket traffic from local address, e.g., 127.0.0.1:8080, to the proxy"))
+            .after_help("With --listen, tools that target the robot API at a local address can be\nused unchanged. The relay runs until it is interrupted, e.g., with Ctrl-C.")
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let instance_id = matches
+            .value_of("instance_id")
+            .ok_or("ID argument is required")?;
+        let status = super::activate_addon(ctx, "mistyproxy", instance_id)?;
+        let urls: Vec<&str> = match &status["url"] {
+            serde_json::Value::Array(urls) => urls.iter().filter_map(|u| u.as_str()).collect(),
+            serde_json::Value::String(url) => vec![url.as_str()],
+            _ => vec![],
+        };
+        if urls.is_empty() {
+            return CliError::new("mistyproxy add-on did not provide proxy URLs", 1);
+        }
+        for url in urls.iter() {
+            println!("{url}");
+        }
+
+        let address = match matches.value_of("listen") {
+            Some(a) => a,
+            None => return Ok(()),
+        };
+        let http = urls
+            .iter()
+            .find(|u| u.starts_with("http"))
+            .unwrap_or(&urls[0]);
+        let websocket = match urls.iter().find(|u| u.starts_with("ws")) {
+            Some(u) => Some(Upstream::parse(u).map_err(|err| CliError::from(err.to_string()))?),
+            None => None,
+        };
+        let upstream = Upstream::parse(http).map_err(|err| CliError::from(err.to_string()))?;
+        match relay::run(address, upstream, websocket, |local| {
+            eprintln!("Relaying http://{local} to {http}; press Ctrl-C to stop");
+        }) {
+            Ok(()) => Ok(()),
+            Err(err) => CliError::new_std(err, 1),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context, FileProgress};
+use crate::drive;
+
+pub struct Pull;
+
+impl super::Subcommand for Pull {
+    fn name(&self) -> &'static str {
+        "pull"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Copy file or directory from instance, via the drive add-on")
+            .arg(
+                Arg::with_name("instance_id")
+                    .value_name("ID")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("remote_path")
+                    .value_name("REMOTE_PATH")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("local_dir")
+                    .value_name("LOCAL_DIR")
+                    .required(true)
+                    .help("directory in which to unpack; it is created if needed"),
+            )
+            .after_help(
+                "The files are received as a tar archive compressed with gzip, and the\n\
+                 archive is unpacked only if its SHA-256 checksum is correct. When done,\n\
+                 the checksum and LOCAL_DIR are printed.",
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let remote_path = matches
+            .value_of("remote_path")
+            .ok_or("REMOTE_PATH is required")?;
+        let local_dir = matches
+            .value_of("local_dir")
+            .ok_or("LOCAL_DIR is required")?;
+
+        let instance_id = super::ready_instance(ctx, matches.value_of("instance_id"))?;
+        super::activate_addon(ctx, "drive", &instance_id)?;
+
+        eprintln!("Receiving archive of {remote_path} from instance {instance_id}");
+        let (archive, expected) = match ctx.client.drive_pull(&instance_id, remote_path) {
+            Ok(r) => r,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        let checksum = drive::sha256_hex(&archive);
+        if checksum != expected {
+            return CliError::new(
+                format!("checksum mismatch: expected {expected}, but received {checksum}"),
+                1,
+            );
+        }
+
+        let mut progress = FileProgress::new("Unpacked");
+        if let Err(err) = drive::unpack(&archive, Path::new(local_dir), |_, size| {
+            progress.update(size)
+        }) {
+            return CliError::new_std(err, 1);
+        }
+        progress.finish(&format!("into {local_dir}"));
+        println!("{checksum}  {local_dir}");
+        Ok(())
+    }
+}
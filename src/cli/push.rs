@@ -0,0 +1,107 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context, FileProgress};
+use crate::drive;
+
+pub struct Push;
+
+impl super::Subcommand for Push {
+    fn name(&self) -> &'static str {
+        "push"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Copy local directory to instance, via the drive add-on")
+            .arg(
+                Arg::with_name("instance_id")
+                    .value_name("ID")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("local_dir")
+                    .value_name("LOCAL_DIR")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("remote_path")
+                    .value_name("REMOTE_PATH")
+                    .help("path on instance; default is the name of LOCAL_DIR"),
+            )
+            .arg(
+                Arg::with_name("no_ignore")
+                    .long("no-ignore")
+                    .help("include files that are ignored by .gitignore"),
+            )
+            .after_help(
+                "The directory is sent as a tar archive compressed with gzip. Files that\n\
+                 are ignored by .gitignore and .git directories are skipped. When done,\n\
+                 the SHA-256 checksum of the archive and REMOTE_PATH are printed.",
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let local_dir = Path::new(
+            matches
+                .value_of("local_dir")
+                .ok_or("LOCAL_DIR is required")?,
+        );
+        let remote_path = match matches.value_of("remote_path") {
+            Some(p) => p.to_string(),
+            None => match local_dir
+                .canonicalize()
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            {
+                Some(name) => name,
+                None => return CliError::new("REMOTE_PATH is required for this LOCAL_DIR", 1),
+            },
+        };
+
+        let instance_id = super::ready_instance(ctx, matches.value_of("instance_id"))?;
+        super::activate_addon(ctx, "drive", &instance_id)?;
+
+        let mut progress = FileProgress::new("Packed");
+        let archive = match drive::pack(local_dir, !matches.is_present("no_ignore"), |_, size| {
+            progress.update(size)
+        }) {
+            Ok(a) => a,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        progress.finish(&format!("into archive of {} bytes", archive.len()));
+        let checksum = drive::sha256_hex(&archive);
+
+        eprintln!("Sending archive to {remote_path} on instance {instance_id}");
+        let payload = match ctx.client.drive_push(&instance_id, &remote_path, &archive) {
+            Ok(p) => p,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        match payload["sha256"].as_str() {
+            Some(received) if received == checksum => {
+                println!("{checksum}  {remote_path}");
+                Ok(())
+            }
+            Some(received) => CliError::new(
+                format!("checksum mismatch: sent {checksum}, but instance received {received}"),
+                1,
+            ),
+            None => CliError::new("instance did not report checksum of received archive", 1),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::{render, PrintingFormat};
+
+pub struct Reservations;
+
+impl super::Subcommand for Reservations {
+    fn name(&self) -> &'static str {
+        "reservations"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("List reservations that are not yet fulfilled")
+            .subcommand(
+                SubCommand::with_name("cancel")
+                    .about("Cancel reservation")
+                    .arg(
+                        Arg::with_name("reservation_id")
+                            .value_name("ID")
+                            .required(true),
+                    ),
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        if let ("cancel", Some(submatches)) = matches.subcommand() {
+            let reservation_id = submatches
+                .value_of("reservation_id")
+                .ok_or("ID argument is required")?;
+            return match ctx.client.cancel_reservation(reservation_id) {
+                Ok(()) => Ok(()),
+                Err(err) => CliError::new_std(err, 1),
+            };
+        }
+
+        let payload = match ctx.client.reservations() {
+            Ok(p) => p,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        if ctx.pformat != PrintingFormat::Default {
+            println!("{}", render(&payload, ctx.pformat));
+            return Ok(());
+        }
+        println!("reservation\t\t\t\tworkspace\t\t\t\tposition");
+        for reservation in payload["reservations"]
+            .as_array()
+            .expect("reservations should be array")
+            .iter()
+        {
+            let workspace = reservation["wdeployment_id"]
+                .as_str()
+                .or(reservation["type"].as_str())
+                .unwrap_or("(unknown)");
+            let position = match reservation["position"].as_u64() {
+                Some(p) => p.to_string(),
+                None => "-".into(),
+            };
+            println!(
+                "{}\t{}\t{}",
+                reservation["id"]
+                    .as_str()
+                    .expect("Reservation ID should be string"),
+                workspace,
+                position
+            );
+        }
+        Ok(())
+    }
+}
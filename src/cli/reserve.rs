@@ -0,0 +1,57 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::launch::read_public_key;
+use super::{CliError, Context};
+
+pub struct Reserve;
+
+impl super::Subcommand for Reserve {
+    fn name(&self) -> &'static str {
+        "reserve"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Reserve workspace deployment or type; an instance is launched when it becomes available")
+            .arg(Arg::with_name("wdid_or_wtype")
+                 .value_name("ID")
+                 .required(true)
+                 .help("workspace type or deployment ID"))
+            .arg(Arg::with_name("public_key")
+                 .long("public-key")
+                 .value_name("FILE")
+                 .help("path of public key to use; if not given, then a new key pair will be generated"))
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let wdid_or_wtype = matches
+            .value_of("wdid_or_wtype")
+            .ok_or("ID or type argument is required")?;
+        let public_key = read_public_key(matches)?;
+        let payload = match ctx.client.reserve(wdid_or_wtype, public_key) {
+            Ok(p) => p,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        println!(
+            "{}",
+            payload["id"]
+                .as_str()
+                .expect("New reservation ID should be string")
+        );
+        Ok(())
+    }
+}
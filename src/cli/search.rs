@@ -0,0 +1,257 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::{render, wdeployments_info, PrintingFormat};
+
+pub struct Search;
+
+const COLUMNS: [&str; 6] = [
+    "online",
+    "locked",
+    "queue",
+    "region",
+    "owner",
+    "description",
+];
+
+impl super::Subcommand for Search {
+    fn name(&self) -> &'static str {
+        "search"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Search for matching deployments. empty query implies show all existing workspace deployments")
+            .arg(Arg::with_name("query")
+                 .value_name("QUERY"))
+            .arg(Arg::with_name("with_user_provided")
+                 .long("include-user-provided")
+                 .help("include user_provided workspace deployments in search"))
+            .arg(Arg::with_name("wtype")
+                 .long("type")
+                 .value_name("TYPE")
+                 .multiple(true)
+                 .number_of_values(1)
+                 .help("only workspace deployments of type TYPE; can be given multiple times"))
+            .arg(Arg::with_name("region")
+                 .long("region")
+                 .value_name("REGION")
+                 .help("only workspace deployments in REGION, e.g., us or us:cali"))
+            .arg(Arg::with_name("owner")
+                 .long("owner")
+                 .value_name("NAME")
+                 .help("only workspace deployments owned by user or organization NAME"))
+            .arg(Arg::with_name("online")
+                 .long("online")
+                 .help("only workspace deployments that are online"))
+            .arg(Arg::with_name("unlocked")
+                 .long("unlocked")
+                 .help("only workspace deployments that are not locked"))
+            .arg(Arg::with_name("max_queue")
+                 .long("max-queue")
+                 .value_name("N")
+                 .help("only workspace deployments with at most N in queue"))
+            .arg(Arg::with_name("sort")
+                 .long("sort")
+                 .value_name("KEY")
+                 .possible_values(&["type", "availability"])
+                 .help("sort by type, or by availability: online and unlocked first, then shortest queue"))
+            .arg(Arg::with_name("columns")
+                 .long("columns")
+                 .value_name("LIST")
+                 .use_delimiter(true)
+                 .possible_values(&COLUMNS)
+                 .help("comma-separated list of extra columns to print"))
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let query = matches.value_of("query");
+        let type_constraint: Option<Vec<&str>> = match matches.values_of("wtype") {
+            Some(types) => Some(types.collect()),
+            None => {
+                if matches.is_present("with_user_provided") {
+                    None
+                } else {
+                    Some(vec!["!user_provided"])
+                }
+            }
+        };
+        let max_queue = match matches.value_of("max_queue") {
+            Some(n) => match n.parse::<u64>() {
+                Ok(n) => Some(n),
+                Err(_) => return CliError::new(format!("invalid queue length: {n}"), 1),
+            },
+            None => None,
+        };
+        let columns: Vec<&str> = matches.values_of("columns").unwrap_or_default().collect();
+
+        let payload = match ctx.client.search(query, type_constraint.as_ref()) {
+            Ok(p) => p,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        let mut deployments: Vec<(String, serde_json::Value)> = vec![];
+        for wd in payload["workspace_deployments"]
+            .as_array()
+            .expect("workspace_deployments should be array")
+            .iter()
+        {
+            let wd = wd
+                .as_str()
+                .expect("Elements of workspace_deployments should be strings");
+            deployments.push((wd.to_string(), payload["info"][wd].clone()));
+        }
+
+        if let Some(region) = matches.value_of("region") {
+            deployments.retain(|(_, info)| in_region(info, region));
+        }
+
+        // Details, e.g., current queue length, are from the deployment itself
+        let needs_details = !columns.is_empty()
+            || max_queue.is_some()
+            || ["owner", "online", "unlocked"]
+                .iter()
+                .any(|a| matches.is_present(a))
+            || matches.value_of("sort") == Some("availability");
+        if needs_details {
+            let ids: Vec<&str> = deployments.iter().map(|(wd, _)| wd.as_str()).collect();
+            let details = wdeployments_info(&ctx.client, &ids);
+            let mut found = vec![];
+            for ((wd, mut info), detail) in deployments.into_iter().zip(details) {
+                let detail = match detail {
+                    Ok(serde_json::Value::Object(d)) => d,
+                    Ok(_) => {
+                        eprintln!("warning: skipping {wd}: details are not an object");
+                        continue;
+                    }
+                    Err(err) => {
+                        eprintln!("warning: skipping {wd}: {err}");
+                        continue;
+                    }
+                };
+                if !info.is_object() {
+                    info = serde_json::Value::Object(serde_json::Map::new());
+                }
+                if let Some(info) = info.as_object_mut() {
+                    info.extend(detail);
+                }
+                found.push((wd, info));
+            }
+            deployments = found;
+        }
+
+        deployments.retain(|(_, info)| {
+            if let Some(owner) = matches.value_of("owner") {
+                if info["owner"].as_str() != Some(owner) {
+                    return false;
+                }
+            }
+            if matches.is_present("online") && !is_online(info) {
+                return false;
+            }
+            if matches.is_present("unlocked") && is_locked(info) {
+                return false;
+            }
+            if let Some(n) = max_queue {
+                if queue_length(info) > n {
+                    return false;
+                }
+            }
+            true
+        });
+
+        match matches.value_of("sort") {
+            Some("type") => {
+                deployments.sort_by(|(_, a), (_, b)| a["type"].as_str().cmp(&b["type"].as_str()))
+            }
+            Some("availability") => deployments
+                .sort_by_key(|(_, info)| (!is_online(info), is_locked(info), queue_length(info))),
+            _ => {}
+        }
+
+        if ctx.pformat != PrintingFormat::Default {
+            let mut info = serde_json::Map::new();
+            for (wd, wdinfo) in deployments.iter() {
+                info.insert(wd.clone(), wdinfo.clone());
+            }
+            let result = serde_json::json!({
+                "workspace_deployments": deployments.iter().map(|(wd, _)| wd).collect::<Vec<_>>(),
+                "info": info,
+            });
+            println!("{}", render(&result, ctx.pformat));
+            return Ok(());
+        }
+
+        for (wd, info) in deployments.iter() {
+            let wtype = info["type"]
+                .as_str()
+                .expect("info.wd.type should be string");
+            let mut line = format!("{wd}    {wtype}");
+            for column in columns.iter() {
+                line.push_str("    ");
+                line.push_str(&column_value(info, column));
+            }
+            println!("{line}");
+        }
+        Ok(())
+    }
+}
+
+fn in_region(info: &serde_json::Value, region: &str) -> bool {
+    match info["region"].as_str() {
+        Some(r) => r == region || r.starts_with(&format!("{region}:")),
+        None => false,
+    }
+}
+
+fn is_online(info: &serde_json::Value) -> bool {
+    info["online"].as_bool().unwrap_or(false)
+}
+
+fn is_locked(info: &serde_json::Value) -> bool {
+    info["lockout"].as_bool().unwrap_or(false)
+}
+
+fn queue_length(info: &serde_json::Value) -> u64 {
+    info["queuelen"].as_u64().unwrap_or(0)
+}
+
+fn column_value(info: &serde_json::Value, column: &str) -> String {
+    match column {
+        "online" => {
+            if is_online(info) {
+                "online".into()
+            } else {
+                "offline".into()
+            }
+        }
+        "locked" => {
+            if is_locked(info) {
+                "locked".into()
+            } else {
+                "unlocked".into()
+            }
+        }
+        "queue" => queue_length(info).to_string(),
+        "region" => info["region"].as_str().unwrap_or("-").into(),
+        "owner" => info["owner"].as_str().unwrap_or("-").into(),
+        "description" => match info["desc"].as_str() {
+            Some(d) if !d.is_empty() => d.into(),
+            _ => "-".into(),
+        },
+        _ => "-".into(),
+    }
+}
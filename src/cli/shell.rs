@@ -0,0 +1,61 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::cmdsh::{self, Mode};
+
+pub struct Shell;
+
+impl super::Subcommand for Shell {
+    fn name(&self) -> &'static str {
+        "shell"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Open shell on instance host via the cmdsh add-on, without ssh")
+            .arg(Arg::with_name("instance_id").value_name("ID"))
+            .arg(super::selector_arg())
+            .arg(
+                Arg::with_name("command")
+                    .short("c")
+                    .value_name("CMD")
+                    .help("run CMD instead of an interactive shell"),
+            )
+            .arg(super::auto_extend_arg())
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let instance_id = super::instance_id_from(matches, ctx)?;
+        let instance_id = super::ready_instance(ctx, instance_id.as_deref())?;
+        let instance_id = instance_id.as_str();
+
+        let status = super::activate_addon(ctx, "cmdsh", instance_id)?;
+
+        let url = cmdsh::websocket_url(&ctx.client, instance_id, &status);
+        let mode = match matches.value_of("command") {
+            Some(command) => Mode::Command(command),
+            None => Mode::Interactive,
+        };
+        match super::with_auto_extend(matches, ctx, instance_id, || {
+            cmdsh::attach(&ctx.client, &url, mode)
+        }) {
+            Ok(0) => Ok(()),
+            Ok(code) => CliError::newrc(code),
+            Err(err) => CliError::new_std(err, 1),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::{agent, ssh_command_for};
+
+pub struct Ssh;
+
+impl super::Subcommand for Ssh {
+    fn name(&self) -> &'static str {
+        "ssh"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        let app = SubCommand::with_name(self.name())
+            .about("Connect to instance host via ssh")
+            .arg(Arg::with_name("instance_id").value_name("ID"))
+            .arg(super::selector_arg())
+            .arg(super::auto_extend_arg())
+            .arg(
+                Arg::with_name("ssh_args")
+                    .required(false)
+                    .multiple(true)
+                    .last(true),
+            );
+        #[cfg(feature = "native-ssh")]
+        let app = app.arg(super::native_arg());
+        app
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let instance_id = super::instance_id_from(matches, ctx)?;
+        let payload = match ctx.client.instance_info(instance_id.as_deref()) {
+            Ok(p) => p,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        let args: Vec<&str> = match matches.values_of("ssh_args") {
+            Some(v) => v.collect(),
+            None => vec![],
+        };
+        let instance_id = payload["id"].as_str().ok_or("id should be string")?;
+        let secret_key_path = super::default_secret_key(instance_id);
+        let secret_key_path = secret_key_path.as_deref();
+        #[cfg(feature = "native-ssh")]
+        if super::use_native_ssh(matches) {
+            if !args.is_empty() {
+                return CliError::new(
+                    "ssh arguments are not supported by the built-in SSH client",
+                    1,
+                );
+            }
+            return super::run_native_ssh(
+                matches,
+                ctx,
+                &payload,
+                secret_key_path,
+                crate::native_ssh::Mode::Interactive,
+            );
+        }
+        // Without a key file, the key is loaded into ssh-agent, if there is one
+        if secret_key_path.is_none() && agent::is_available() {
+            match agent::has_instance_key(instance_id) {
+                Ok(true) => {}
+                Ok(false) => match super::add_to_agent(ctx, &payload) {
+                    Ok(_) => info!("added secret key of instance {instance_id} to ssh-agent"),
+                    Err(err) => warn!("cannot add secret key to ssh-agent: {err}"),
+                },
+                Err(err) => warn!("{err}"),
+            }
+        }
+        let mut cmd = match ssh_command_for(&payload, secret_key_path, &args) {
+            Ok(c) => c,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+
+        let status = match super::with_auto_extend(matches, ctx, instance_id, || {
+            cmd.stdin(std::process::Stdio::inherit())
+                .stdout(std::process::Stdio::inherit())
+                .status()
+        }) {
+            Ok(rc) => rc,
+            Err(err) => return CliError::new_stdio(err, 1),
+        };
+        if status.success() {
+            Ok(())
+        } else {
+            CliError::newrc(1)
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::agent;
+use crate::state::State;
+
+pub struct Terminate;
+
+impl super::Subcommand for Terminate {
+    fn name(&self) -> &'static str {
+        "terminate"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Terminate instance")
+            .arg(Arg::with_name("instance_id").value_name("ID"))
+            .arg(super::selector_arg().help(
+                "terminate all active instances that have the label, after confirmation if there are more than one; can be given more than once",
+            ))
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let instance_ids = match super::select_by_labels(matches, ctx)? {
+            Some(selected) => {
+                if selected.len() > 1 {
+                    eprintln!("Selected {} instances:", selected.len());
+                    for instance_id in selected.iter() {
+                        eprintln!("  {instance_id}");
+                    }
+                    let prompt = format!("Terminate all {} instances? [y/N]", selected.len());
+                    if !super::confirm(ctx, &prompt)? {
+                        return CliError::new(
+                            "Error: not terminating more than one instance without confirmation; give -y to confirm",
+                            1,
+                        );
+                    }
+                }
+                selected
+            }
+            None => match ctx.client.select_instance(matches.value_of("instance_id")) {
+                Ok(inid) => vec![inid],
+                Err(err) => return CliError::new_std(err, 1),
+            },
+        };
+        for instance_id in instance_ids.iter() {
+            if let Err(err) = ctx.client.terminate(Some(instance_id)) {
+                return CliError::new_std(err, 1);
+            }
+            forget(instance_id);
+            remove_from_agent(instance_id);
+        }
+        Ok(())
+    }
+}
+
+/// Remove local state about terminated instance.
+fn forget(instance_id: &str) {
+    let result = State::load().and_then(|mut local| {
+        local.remove(instance_id);
+        local.save()
+    });
+    if let Err(err) = result {
+        eprintln!("warning: cannot update local state: {err}");
+    }
+}
+
+/// Remove secret key of terminated instance from ssh-agent, if it was added.
+fn remove_from_agent(instance_id: &str) {
+    if !agent::is_available() {
+        return;
+    }
+    match agent::remove_instance_keys(instance_id) {
+        Ok(0) => {}
+        Ok(_) => eprintln!("Removed secret key of instance {instance_id} from ssh-agent"),
+        Err(err) => eprintln!("warning: cannot remove secret key from ssh-agent: {err}"),
+    }
+}
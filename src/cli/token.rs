@@ -0,0 +1,451 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{read_token_file, CliError, Context};
+use crate::claims::{unix_now, Claims};
+use crate::{
+    credentials, duration, format_timestamp, public_key, render, PrintingFormat, TokenSource,
+};
+
+pub struct Token;
+
+impl super::Subcommand for Token {
+    fn name(&self) -> &'static str {
+        "token"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Get information about an API token")
+            .arg(Arg::with_name("token_file")
+                 .value_name("FILE")
+                 .help("plaintext file containing API token; if not given, use the API token of `-t`, REROBOTS_API_TOKEN, or the active profile"))
+            .arg(Arg::with_name("verify")
+                 .long("verify")
+                 .help("verify signature of the token using the public key of rerobots, which is fetched once and cached"))
+            .arg(Arg::with_name("pubkey")
+                 .long("pubkey")
+                 .value_name("FILE")
+                 .help("verify signature using the public key (PEM) in FILE instead of fetching it; implies --verify"))
+            .subcommand(SubCommand::with_name("store")
+                        .about("Store API token for the active profile in the keyring or an encrypted file")
+                        .arg(Arg::with_name("token_file")
+                             .value_name("FILE")
+                             .help("plaintext file containing API token; if not given, read from stdin"))
+                        .arg(backend_arg()))
+            .subcommand(SubCommand::with_name("clear")
+                        .about("Delete stored API token of the active profile")
+                        .arg(backend_arg()))
+            .subcommand(SubCommand::with_name("create")
+                        .about("Create API token and print it")
+                        .arg(expires_arg())
+                        .arg(scope_arg()))
+            .subcommand(SubCommand::with_name("list")
+                        .about("List API tokens"))
+            .subcommand(SubCommand::with_name("revoke")
+                        .about("Revoke API token")
+                        .arg(Arg::with_name("token_id")
+                             .value_name("ID")
+                             .required(true)))
+            .subcommand(SubCommand::with_name("rotate")
+                        .about("Create API token, store it for the active profile, and revoke the current token")
+                        .arg(expires_arg()
+                             .help("duration until new token expires, e.g., 30d; default is the lifetime of the current token"))
+                        .arg(scope_arg()
+                             .help("scope of new token; can be given multiple times; default is scopes of the current token"))
+                        .arg(backend_arg())
+                        .arg(Arg::with_name("force")
+                             .long("force")
+                             .help("rotate even if the current token is not the stored token, e.g., it is from REROBOTS_API_TOKEN")))
+    }
+
+    /// `store` and `clear` manage stored tokens without calling the API, and
+    /// a token in FILE is inspected without the stored token.
+    fn uses_api_token(&self, matches: &clap::ArgMatches) -> bool {
+        match matches.subcommand_name() {
+            Some("store") | Some("clear") => false,
+            Some(_) => true,
+            None => !matches.is_present("token_file") || fetches_public_key(matches),
+        }
+    }
+
+    /// Tokens are only inspected without subcommands, so expired tokens can
+    /// be shown, unless the public key is fetched from the API to verify.
+    fn checks_api_token(&self, matches: &clap::ArgMatches) -> bool {
+        match matches.subcommand_name() {
+            Some("store") | Some("clear") => false,
+            Some(_) => true,
+            None => fetches_public_key(matches),
+        }
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        match matches.subcommand() {
+            ("store", Some(submatches)) => return store(submatches, ctx),
+            ("clear", Some(submatches)) => return clear(submatches, ctx),
+            ("create", Some(submatches)) => return create(submatches, ctx),
+            ("list", Some(_)) => return list(ctx),
+            ("revoke", Some(submatches)) => return revoke(submatches, ctx),
+            ("rotate", Some(submatches)) => return rotate(submatches, ctx),
+            _ => {}
+        }
+        let (api_token, source) = match matches.value_of("token_file") {
+            Some(fname) => (Some(read_token_file(fname)?), None),
+            None => (ctx.api_token.clone(), ctx.token_source.as_ref()),
+        };
+        let api_token = match api_token {
+            Some(a) => a,
+            None => {
+                return CliError::new("No API token given", 1);
+            }
+        };
+
+        let verify = matches.is_present("verify") || matches.is_present("pubkey");
+        show_claims(&api_token, source, verify, matches.value_of("pubkey"), ctx)
+    }
+}
+
+/// Whether the token is verified with the public key of rerobots from the API
+fn fetches_public_key(matches: &clap::ArgMatches) -> bool {
+    matches.is_present("verify") && !matches.is_present("pubkey")
+}
+
+fn verify_token(
+    api_token: &str,
+    pubkey_file: Option<&str>,
+    ctx: &Context,
+) -> Result<Claims, Box<dyn std::error::Error>> {
+    if let Some(fname) = pubkey_file {
+        let key = std::fs::read(fname).map_err(|err| format!("{fname}: {err}"))?;
+        return Claims::verify(api_token, &key);
+    }
+    let key = public_key(&ctx.client, false)?;
+    match Claims::verify(api_token, &key) {
+        Ok(claims) => Ok(claims),
+        Err(err) => {
+            // The cached key may be outdated
+            let fresh_key = public_key(&ctx.client, true)?;
+            if fresh_key == key {
+                Err(err)
+            } else {
+                Claims::verify(api_token, &fresh_key)
+            }
+        }
+    }
+}
+
+fn show_claims(
+    api_token: &str,
+    source: Option<&TokenSource>,
+    verify: bool,
+    pubkey_file: Option<&str>,
+    ctx: &Context,
+) -> Result<(), CliError> {
+    let claims = if verify {
+        verify_token(api_token, pubkey_file, ctx)
+            .map_err(|err| CliError::from(format!("Error: cannot verify API token: {err}")))?
+    } else {
+        Claims::decode(api_token).map_err(|err| CliError::from(format!("{err}")))?
+    };
+    let remaining = claims.remaining().map(|r| r.as_secs());
+
+    if ctx.pformat == PrintingFormat::Default {
+        let or = |x: &Option<String>, default: &str| x.clone().unwrap_or(default.into());
+        println!("subject: {}", or(&claims.subject, "(none)"));
+        println!("organization: {}", or(&claims.organization, "(none)"));
+        println!(
+            "issued: {}",
+            or(&claims.issued_at.map(format_timestamp), "(unknown)")
+        );
+        println!(
+            "expiration: {}",
+            or(&claims.expiration.map(format_timestamp), "(never)")
+        );
+        if let Some(r) = remaining {
+            println!(
+                "remaining: {}",
+                duration::format(std::time::Duration::from_secs(r))
+            );
+        }
+        if claims.scopes.is_empty() {
+            println!("scopes: (all)");
+        } else {
+            println!("scopes: {}", claims.scopes.join(", "));
+        }
+        if verify {
+            println!("signature: valid");
+        }
+        if let Some(source) = source {
+            println!("source: {source}");
+        }
+        if claims.is_expired() {
+            println!("warning: This token is expired.");
+            return CliError::newrc(1);
+        }
+    } else {
+        let payload = serde_json::json!({
+            "id": claims.id,
+            "subject": claims.subject,
+            "organization": claims.organization,
+            "issued_at": claims.issued_at.map(format_timestamp),
+            "expiration": claims.expiration.map(format_timestamp),
+            "remaining_seconds": remaining,
+            "scopes": claims.scopes,
+            "verified": verify,
+            "source": source.map(|s| s.to_string()),
+        });
+        println!("{}", render(&payload, ctx.pformat));
+        if claims.is_expired() {
+            eprintln!("warning: This token is expired.");
+            return CliError::newrc(1);
+        }
+    }
+    Ok(())
+}
+
+fn backend_arg() -> Arg<'static, 'static> {
+    Arg::with_name("backend")
+        .long("backend")
+        .value_name("NAME")
+        .help("where to store tokens; options: secret-service , file (default: credential_backend of profile, else secret-service if available, else file)")
+}
+
+fn expires_arg() -> Arg<'static, 'static> {
+    Arg::with_name("expires")
+        .long("expires")
+        .value_name("DURATION")
+        .help("duration until token expires, e.g., 30d or 12h; if not given, the token does not expire")
+}
+
+fn scope_arg() -> Arg<'static, 'static> {
+    Arg::with_name("scope")
+        .long("scope")
+        .value_name("SCOPE")
+        .multiple(true)
+        .number_of_values(1)
+        .help("restrict token to SCOPE; can be given multiple times")
+}
+
+fn expiration_from_matches(matches: &clap::ArgMatches) -> Result<Option<u64>, CliError> {
+    match matches.value_of("expires") {
+        Some(given) => match duration::parse(given) {
+            Ok(d) => Ok(Some(unix_now() + d.as_secs())),
+            Err(err) => Err(CliError::from(format!("{err}"))),
+        },
+        None => Ok(None),
+    }
+}
+
+fn open_store(
+    matches: &clap::ArgMatches,
+    ctx: &Context,
+) -> Result<Box<dyn credentials::CredentialStore>, CliError> {
+    let backend = matches
+        .value_of("backend")
+        .or(ctx.profile.credential_backend.as_deref());
+    credentials::open(backend).map_err(|err| CliError::from(format!("{err}")))
+}
+
+fn store(matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+    let api_token = match matches.value_of("token_file") {
+        Some(fname) => read_token_file(fname)?,
+        None => {
+            let mut buf = String::new();
+            if let Err(err) = std::io::stdin().read_line(&mut buf) {
+                return CliError::new_stdio(err, 1);
+            }
+            buf.trim().to_string()
+        }
+    };
+    if api_token.is_empty() {
+        return CliError::new("No API token given", 1);
+    }
+
+    let store = open_store(matches, ctx)?;
+    let name = ctx.credential_name();
+    if let Err(err) = store.set(name, &api_token) {
+        return CliError::new_std(err, 1);
+    }
+    eprintln!("Stored API token for profile {name} in {}", store.name());
+    Ok(())
+}
+
+fn clear(matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+    let store = open_store(matches, ctx)?;
+    let name = ctx.credential_name();
+    match store.delete(name) {
+        Ok(true) => {
+            eprintln!("Deleted API token for profile {name} from {}", store.name());
+            Ok(())
+        }
+        Ok(false) => CliError::new(
+            format!("No API token stored for profile {name} in {}", store.name()),
+            1,
+        ),
+        Err(err) => CliError::new_std(err, 1),
+    }
+}
+
+fn create(matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+    let expiration = expiration_from_matches(matches)?;
+    let scopes: Vec<&str> = matches.values_of("scope").unwrap_or_default().collect();
+    let payload = match ctx.client.create_token(expiration, &scopes) {
+        Ok(p) => p,
+        Err(err) => return CliError::new_std(err, 1),
+    };
+    if ctx.pformat == PrintingFormat::Default {
+        match payload["token"].as_str() {
+            Some(tok) => println!("{tok}"),
+            None => return CliError::new("response does not include token", 1),
+        }
+    } else {
+        println!("{}", render(&payload, ctx.pformat));
+    }
+    Ok(())
+}
+
+fn list(ctx: &Context) -> Result<(), CliError> {
+    let payload = match ctx.client.tokens() {
+        Ok(p) => p,
+        Err(err) => return CliError::new_std(err, 1),
+    };
+    if ctx.pformat != PrintingFormat::Default {
+        println!("{}", render(&payload, ctx.pformat));
+        return Ok(());
+    }
+    println!("token\texpiration\t\tscopes");
+    for tok in payload["tokens"]
+        .as_array()
+        .ok_or("tokens should be array")?
+        .iter()
+    {
+        let expiration = match tok["expiration"].as_u64() {
+            Some(exp) => format_timestamp(exp),
+            None => "(never)\t\t".into(),
+        };
+        let scopes: Vec<&str> = tok["scopes"]
+            .as_array()
+            .map(|a| a.iter().filter_map(|s| s.as_str()).collect())
+            .unwrap_or_default();
+        let scopes = if scopes.is_empty() {
+            "(all)".into()
+        } else {
+            scopes.join(",")
+        };
+        println!(
+            "{}\t{}\t{}",
+            tok["id"].as_str().ok_or("token ID should be string")?,
+            expiration,
+            scopes
+        );
+    }
+    Ok(())
+}
+
+fn revoke(matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+    let token_id = matches.value_of("token_id").ok_or("ID is required")?;
+    match ctx.client.revoke_token(token_id) {
+        Ok(()) => Ok(()),
+        Err(err) => CliError::new_std(err, 1),
+    }
+}
+
+fn rotate(matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+    let old_token = match ctx.api_token.clone() {
+        Some(tok) => tok,
+        None => return CliError::new("No API token given", 1),
+    };
+    let claims = match Claims::decode(&old_token) {
+        Ok(c) => c,
+        Err(err) => return CliError::new(format!("cannot rotate current API token: {err}"), 1),
+    };
+    let old_id = match &claims.id {
+        Some(id) => id.clone(),
+        None => return CliError::new("cannot rotate current API token: it does not have an ID", 1),
+    };
+    // The new token is stored, so it would not be used while a token from
+    // elsewhere takes precedence.
+    match &ctx.token_source {
+        Some(TokenSource::Stored(..)) | None => {}
+        Some(source) if !matches.is_present("force") => {
+            return CliError::new(
+                format!("cannot rotate API token from {source}, which takes precedence over the stored token; give --force to rotate anyway"),
+                1,
+            )
+        }
+        Some(_) => {}
+    }
+
+    let expiration = match expiration_from_matches(matches)? {
+        Some(exp) => Some(exp),
+        None => match (claims.issued_at, claims.expiration) {
+            (Some(iat), Some(exp)) => Some(unix_now() + exp.saturating_sub(iat)),
+            _ => None,
+        },
+    };
+    let scopes: Vec<&str> = match matches.values_of("scope") {
+        Some(s) => s.collect(),
+        None => claims.scopes.iter().map(|s| s.as_str()).collect(),
+    };
+
+    let store = open_store(matches, ctx)?;
+    let payload = match ctx.client.create_token(expiration, &scopes) {
+        Ok(p) => p,
+        Err(err) => return CliError::new_std(err, 1),
+    };
+    let new_token = payload["token"]
+        .as_str()
+        .ok_or("response does not include token")?;
+    let new_id = payload["id"].as_str().unwrap_or("(unknown)");
+    let name = ctx.credential_name();
+    if let Err(err) = store.set(name, new_token) {
+        println!("{new_token}");
+        return CliError::new(
+            format!(
+                "cannot store new API token, so the current token {old_id} is not revoked: {err}"
+            ),
+            1,
+        );
+    }
+    if let Err(err) = ctx.client.revoke_token(&old_id) {
+        return CliError::new(
+            format!("stored new API token {new_id}, but cannot revoke the current token {old_id}: {err}"),
+            1,
+        );
+    }
+
+    if ctx.pformat == PrintingFormat::Default {
+        eprintln!(
+            "Stored new API token {new_id} for profile {name} in {}, and revoked {old_id}",
+            store.name()
+        );
+    } else {
+        let result = serde_json::json!({
+            "id": new_id,
+            "profile": name,
+            "backend": store.name(),
+            "revoked": old_id,
+        });
+        println!("{}", render(&result, ctx.pformat));
+    }
+    match &ctx.token_source {
+        Some(TokenSource::Stored(..)) | None => {}
+        Some(source) => {
+            eprintln!("warning: API token from {source} takes precedence over the stored token")
+        }
+    }
+    Ok(())
+}
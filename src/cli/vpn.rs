@@ -0,0 +1,191 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::{Path, PathBuf};
+
+use clap::{AppSettings, Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::vpn::{self, Kind};
+use crate::{render, write_secret_key, PrintingFormat};
+
+pub struct Vpn;
+
+impl super::Subcommand for Vpn {
+    fn name(&self) -> &'static str {
+        "vpn"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Manage VPN clients of instance, via the vpn add-on")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .arg(Arg::with_name("instance_id")
+                 .value_name("ID")
+                 .required(true))
+            .subcommand(SubCommand::with_name("create-client")
+                        .about("Create VPN client and write its configuration")
+                        .arg(Arg::with_name("client_name")
+                             .value_name("NAME")
+                             .required(true))
+                        .arg(Arg::with_name("config_path")
+                             .short("f")
+                             .value_name("FILE")
+                             .help("file in which to write the configuration (default NAME.ovpn, or NAME.conf for WireGuard)")))
+            .subcommand(SubCommand::with_name("list")
+                        .about("List VPN clients"))
+            .subcommand(SubCommand::with_name("revoke")
+                        .about("Revoke VPN client")
+                        .arg(Arg::with_name("client_name")
+                             .value_name("NAME")
+                             .required(true)))
+            .subcommand(SubCommand::with_name("up")
+                        .about("Connect to the instance network until interrupted")
+                        .after_help("If no configuration is given, then a temporary client is created and revoked when the connection is closed. OpenVPN configurations are run with `openvpn`, and WireGuard configurations (*.conf) with `wg-quick`, which must be in PATH.")
+                        .arg(Arg::with_name("config_path")
+                             .long("config")
+                             .value_name("FILE")
+                             .help("configuration from `create-client`"))
+                        .arg(Arg::with_name("client_name")
+                             .long("name")
+                             .value_name("NAME")
+                             .conflicts_with("config_path")
+                             .help("name of temporary client (default cli-PID)")))
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let instance_id = matches
+            .value_of("instance_id")
+            .ok_or("ID argument is required")?;
+        match matches.subcommand() {
+            ("create-client", Some(submatches)) => {
+                let name = submatches
+                    .value_of("client_name")
+                    .ok_or("NAME argument is required")?;
+                super::activate_addon(ctx, "vpn", instance_id)?;
+                let (config, kind) = create_client(ctx, instance_id, name)?;
+                let path = match submatches.value_of("config_path") {
+                    Some(p) => PathBuf::from(p),
+                    None => PathBuf::from(format!("{name}.{}", kind.extension())),
+                };
+                write_config(&path, &config)?;
+                eprintln!(
+                    "Wrote configuration of VPN client {name} to {}",
+                    path.display()
+                );
+                Ok(())
+            }
+            ("list", _) => {
+                let status = match ctx.client.addon_status("vpn", instance_id) {
+                    Ok(Some(s)) => s,
+                    Ok(None) => return CliError::new("vpn add-on is not enabled", 1),
+                    Err(err) => return CliError::new_std(err, 1),
+                };
+                let clients = match status["clients"].as_array() {
+                    Some(c) => c.clone(),
+                    None => vec![],
+                };
+                match ctx.pformat {
+                    PrintingFormat::Default => {
+                        for client in clients.iter() {
+                            match client.as_str() {
+                                Some(name) => println!("{name}"),
+                                None => println!("{}", client["name"].as_str().unwrap_or("")),
+                            }
+                        }
+                    }
+                    pformat => println!("{}", render(&clients.into(), pformat)),
+                }
+                Ok(())
+            }
+            ("revoke", Some(submatches)) => {
+                let name = submatches
+                    .value_of("client_name")
+                    .ok_or("NAME argument is required")?;
+                match ctx.client.revoke_vpn_client(instance_id, name) {
+                    Ok(()) => Ok(()),
+                    Err(err) => CliError::new_std(err, 1),
+                }
+            }
+            ("up", Some(submatches)) => match submatches.value_of("config_path") {
+                Some(path) => {
+                    let path = Path::new(path);
+                    finish(vpn::up(path, Kind::from_path(path)))
+                }
+                None => {
+                    let name = match submatches.value_of("client_name") {
+                        Some(n) => n.to_string(),
+                        None => format!("cli-{}", std::process::id()),
+                    };
+                    up_temporary(ctx, instance_id, &name)
+                }
+            },
+            _ => CliError::new("unknown subcommand", 1),
+        }
+    }
+}
+
+fn create_client(ctx: &Context, instance_id: &str, name: &str) -> Result<(String, Kind), CliError> {
+    let payload = ctx
+        .client
+        .create_vpn_client(instance_id, name)
+        .map_err(|err| CliError::from(err.to_string()))?;
+    let config = payload["config"]
+        .as_str()
+        .ok_or("VPN client configuration should be string")?;
+    let kind = Kind::from_type(payload["type"].as_str().unwrap_or("openvpn"))
+        .map_err(|err| CliError::from(err.to_string()))?;
+    Ok((config.to_string(), kind))
+}
+
+fn write_config(path: &Path, config: &str) -> Result<(), CliError> {
+    let fname = path.to_str().ok_or("path should be valid string")?;
+    write_secret_key(fname, config).map_err(|err| CliError::from(format!("{fname}: {err}")))
+}
+
+/// Create client, connect with it, and revoke it after disconnecting.
+fn up_temporary(ctx: &Context, instance_id: &str, name: &str) -> Result<(), CliError> {
+    super::activate_addon(ctx, "vpn", instance_id)?;
+    let (config, kind) = create_client(ctx, instance_id, name)?;
+    // The configuration includes a secret key, so it is written in a new
+    // directory that only the current user can access. wg-quick takes the
+    // interface name from the file name.
+    let result = tempfile::Builder::new()
+        .prefix("rerobots-vpn")
+        .tempdir()
+        .map_err(|err| CliError::from(err.to_string()))
+        .and_then(|dir| {
+            let path = dir
+                .path()
+                .join(format!("rrvpn{}", std::process::id()))
+                .with_extension(kind.extension());
+            let result = write_config(&path, &config).map(|()| vpn::up(&path, kind));
+            if let Err(err) = dir.close() {
+                warn!("cannot remove temporary configuration: {}", err);
+            }
+            result
+        });
+    if let Err(err) = ctx.client.revoke_vpn_client(instance_id, name) {
+        eprintln!("warning: cannot revoke VPN client {name}: {err}");
+    }
+    finish(result?)
+}
+
+fn finish(result: Result<i32, Box<dyn std::error::Error>>) -> Result<(), CliError> {
+    match result {
+        Ok(0) => Ok(()),
+        Ok(code) => CliError::newrc(code),
+        Err(err) => CliError::new_std(err, 1),
+    }
+}
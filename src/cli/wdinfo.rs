@@ -0,0 +1,216 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Arg, SubCommand};
+
+use super::{CliError, Context};
+use crate::claims::unix_now;
+use crate::{duration, format_timestamp, is_transient, render, PrintingFormat};
+
+pub struct Wdinfo;
+
+impl super::Subcommand for Wdinfo {
+    fn name(&self) -> &'static str {
+        "wdinfo"
+    }
+
+    fn app(&self) -> clap::App<'static, 'static> {
+        SubCommand::with_name(self.name())
+            .about("Print summary about workspace deployment")
+            .arg(
+                Arg::with_name("wdeployment_id")
+                    .value_name("ID")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("watch")
+                    .long("watch")
+                    .help("Poll the workspace deployment and print its availability whenever it changes"),
+            )
+            .arg(
+                Arg::with_name("interval")
+                    .long("interval")
+                    .value_name("DURATION")
+                    .requires("watch")
+                    .help("time between polls with --watch, at least 1s (default 5s)"),
+            )
+            .arg(
+                Arg::with_name("until_available")
+                    .long("until-available")
+                    .requires("watch")
+                    .help("with --watch, exit when the workspace deployment is online, unlocked, and has an empty queue"),
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches, ctx: &Context) -> Result<(), CliError> {
+        let wdeployment_id = matches
+            .value_of("wdeployment_id")
+            .ok_or("ID argument is required")?;
+        if matches.is_present("watch") {
+            return watch(matches, ctx, wdeployment_id);
+        }
+        let payload = match ctx.client.wdeployment_info(wdeployment_id) {
+            Ok(p) => p,
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        if ctx.pformat == PrintingFormat::Default {
+            print!("{}", summary(&payload));
+        } else {
+            println!("{}", render(&payload, ctx.pformat));
+        }
+        Ok(())
+    }
+}
+
+fn list_or(values: &serde_json::Value, none: &str) -> String {
+    let values: Vec<String> = values
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .map(|v| match v.as_str() {
+                    Some(s) => s.to_string(),
+                    None => v.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if values.is_empty() {
+        none.into()
+    } else {
+        values.join(", ")
+    }
+}
+
+fn availability(payload: &serde_json::Value) -> String {
+    let online = if payload["online"].as_bool().unwrap_or(false) {
+        "online"
+    } else {
+        "offline"
+    };
+    let locked = if payload["lockout"].as_bool().unwrap_or(false) {
+        "locked"
+    } else {
+        "unlocked"
+    };
+    format!(
+        "{online}, {locked}, queue {}",
+        payload["queuelen"].as_u64().unwrap_or(0)
+    )
+}
+
+fn is_available(payload: &serde_json::Value) -> bool {
+    payload["online"].as_bool().unwrap_or(false)
+        && !payload["lockout"].as_bool().unwrap_or(false)
+        && payload["queuelen"].as_u64().unwrap_or(0) == 0
+}
+
+fn estimated_wait(payload: &serde_json::Value) -> String {
+    if let Some(seconds) = payload["est_wait"].as_u64() {
+        return duration::format(std::time::Duration::from_secs(seconds));
+    }
+    if is_available(payload) {
+        "none".into()
+    } else {
+        "(unknown)".into()
+    }
+}
+
+fn summary(payload: &serde_json::Value) -> String {
+    let mut lines = vec![];
+    lines.push(format!(
+        "id: {}",
+        payload["id"].as_str().unwrap_or("(unknown)")
+    ));
+    let wtype = payload["type"].as_str().unwrap_or("(unknown)");
+    match payload["type_version"].as_u64() {
+        Some(v) => lines.push(format!("type: {wtype} (version {v})")),
+        None => lines.push(format!("type: {wtype}")),
+    }
+    lines.push(format!(
+        "region: {}",
+        payload["region"].as_str().unwrap_or("(unknown)")
+    ));
+    if let Some(owner) = payload["owner"].as_str() {
+        lines.push(format!("owner: {owner}"));
+    }
+    lines.push(format!("status: {}", availability(payload)));
+    lines.push(format!("estimated wait: {}", estimated_wait(payload)));
+    lines.push(format!(
+        "add-ons: {}",
+        list_or(&payload["supported_addons"], "(none)")
+    ));
+    let cameras: Vec<String> = payload["cameras"]
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .map(|cam| match cam["desc"].as_str() {
+                    Some(d) if !d.is_empty() => format!("{} ({})", cam["id"], d),
+                    _ => cam["id"].to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if cameras.is_empty() {
+        lines.push("cameras: (none)".into());
+    } else {
+        lines.push(format!("cameras: {}", cameras.join(", ")));
+    }
+    match payload["desc"].as_str() {
+        Some(d) if !d.is_empty() => lines.push(format!("description: {d}")),
+        _ => lines.push("description: (none)".into()),
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+fn watch(matches: &clap::ArgMatches, ctx: &Context, wdeployment_id: &str) -> Result<(), CliError> {
+    let interval = match matches.value_of("interval") {
+        Some(given) => match duration::parse(given) {
+            Ok(d) if d < std::time::Duration::from_secs(1) => {
+                return CliError::new("interval must be at least 1s", 1)
+            }
+            Ok(d) => d,
+            Err(err) => return CliError::new_std(err, 1),
+        },
+        None => std::time::Duration::from_secs(5),
+    };
+    let mut last = None;
+    loop {
+        let payload = match ctx.client.wdeployment_info(wdeployment_id) {
+            Ok(p) => p,
+            // Only retry transient errors after the first poll succeeded,
+            // e.g., not if the ID is wrong or the token was revoked
+            Err(err) if last.is_some() && is_transient(err.as_ref()) => {
+                eprintln!("warning: cannot get details of {wdeployment_id}: {err}");
+                std::thread::sleep(interval);
+                continue;
+            }
+            Err(err) => return CliError::new_std(err, 1),
+        };
+        let current = availability(&payload);
+        if last.as_ref() != Some(&current) {
+            if ctx.pformat == PrintingFormat::Default {
+                println!("{}  {}", format_timestamp(unix_now()), current);
+            } else {
+                println!("{}", render(&payload, ctx.pformat));
+            }
+            last = Some(current);
+        }
+        if matches.is_present("until_available") && is_available(&payload) {
+            return Ok(());
+        }
+        std::thread::sleep(interval);
+    }
+}
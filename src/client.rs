@@ -0,0 +1,904 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use actix::prelude::*;
+
+use crate::files::user_only_perm;
+use crate::{fixture, redact};
+
+const DEFAULT_ORIGIN: &str = "https://api.rerobots.net";
+
+/// Maximum size of response bodies, in bytes
+const BODY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Number of exchanges written by `--dump-http` in this process
+static DUMP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+struct ClientError {
+    msg: String,
+
+    /// HTTP status of the response, if the error is indicated by the API
+    status: Option<u16>,
+}
+impl std::error::Error for ClientError {}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl std::fmt::Debug for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl ClientError {
+    fn newbox<T, S: ToString>(msg: S) -> Result<T, Box<dyn std::error::Error>> {
+        Err(Box::new(ClientError {
+            msg: msg.to_string(),
+            status: None,
+        }))
+    }
+
+    fn from_status<T, S: ToString>(status: u16, msg: S) -> Result<T, Box<dyn std::error::Error>> {
+        Err(Box::new(ClientError {
+            msg: msg.to_string(),
+            status: Some(status),
+        }))
+    }
+}
+
+/// Whether a failed call might succeed if it is retried
+///
+/// Errors in responses with status 4xx, e.g., not found or unauthorized, are
+/// not transient, except 429 (too many requests). Others, e.g., 5xx or
+/// network errors, are.
+pub fn is_transient(err: &(dyn std::error::Error + 'static)) -> bool {
+    match err.downcast_ref::<ClientError>().and_then(|e| e.status) {
+        Some(status) => status == 429 || !(400..500).contains(&status),
+        None => true,
+    }
+}
+
+fn parse_error_message(payload: &serde_json::Value) -> String {
+    match payload["error_message"].as_str() {
+        Some(s) => s.to_string(),
+        None => match payload["error"].as_str() {
+            Some(s) => s.to_string(),
+            None => format!("{payload}"),
+        },
+    }
+}
+
+/// Origin of the rerobots API
+///
+/// This is `https://api.rerobots.net` unless the environment variable
+/// `REROBOTS_ORIGIN` is defined, e.g., to use a local stand-in for testing.
+/// If it is not defined when running but was when building, then the origin
+/// given at build time is used, as in earlier releases.
+pub fn default_origin() -> String {
+    match std::env::var("REROBOTS_ORIGIN") {
+        Ok(origin) if !origin.is_empty() => origin.trim_end_matches('/').to_string(),
+        _ => match option_env!("REROBOTS_ORIGIN") {
+            Some(origin) if !origin.is_empty() => origin.trim_end_matches('/').to_string(),
+            _ => DEFAULT_ORIGIN.to_string(),
+        },
+    }
+}
+
+/// Client for the rerobots API
+///
+/// The API token, if any, is given once when the client is created, instead of
+/// being passed to every call.
+///
+/// If the environment variable `REROBOTS_RECORD` is the path of a file, then
+/// every request and response is appended to it in the format of
+/// [`fixture::Exchange`], which can be replayed by a stand-in server.
+pub struct Client {
+    api_token: Option<String>,
+    origin: String,
+    trace: bool,
+    dump_dir: Option<std::path::PathBuf>,
+}
+
+struct RawResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// Request and the response to it, or the error that occurred instead
+struct Exchange<'a> {
+    method: &'a awc::http::Method,
+    url: &'a str,
+    authorization: bool,
+    body: Option<&'a serde_json::Value>,
+    result: &'a Result<RawResponse, Box<dyn std::error::Error>>,
+    latency: std::time::Duration,
+}
+
+impl Exchange<'_> {
+    fn request_id(&self) -> Option<&str> {
+        match self.result {
+            Ok(resp) => resp
+                .headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("x-request-id"))
+                .map(|(_, v)| v.as_str()),
+            Err(_) => None,
+        }
+    }
+
+    /// Print summary to stderr, as for `--trace`.
+    fn trace(&self) {
+        let latency = self.latency.as_millis();
+        match self.result {
+            Ok(resp) => eprintln!(
+                "trace: {} {} -> {} in {} ms; request ID: {}",
+                self.method,
+                redact::text(self.url),
+                resp.status,
+                latency,
+                self.request_id().unwrap_or("(none)")
+            ),
+            Err(err) => eprintln!(
+                "trace: {} {} -> error in {} ms: {}",
+                self.method,
+                redact::text(self.url),
+                latency,
+                err
+            ),
+        }
+    }
+
+    /// Write redacted request and response to a new file in `dir`.
+    fn dump(&self, dir: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut request_headers = serde_json::Map::new();
+        if self.authorization {
+            request_headers.insert(
+                "Authorization".into(),
+                format!("Bearer {}", redact::REDACTED).into(),
+            );
+        }
+        if self.body.is_some() {
+            request_headers.insert("Content-Type".into(), "application/json".into());
+        }
+        let request = serde_json::json!({
+            "method": self.method.as_str(),
+            "url": redact::text(self.url),
+            "headers": request_headers,
+            "body": self.body.map(redact::json),
+        });
+        let response = match self.result {
+            Ok(resp) => {
+                let headers: serde_json::Map<String, serde_json::Value> = resp
+                    .headers
+                    .iter()
+                    .map(|(k, v)| (k.clone(), redact::header(k, v).into()))
+                    .collect();
+                let body = if resp.body.is_empty() {
+                    serde_json::Value::Null
+                } else {
+                    match serde_json::from_slice::<serde_json::Value>(&resp.body) {
+                        Ok(payload) => redact::json(&payload),
+                        Err(_) => redact::text(&String::from_utf8_lossy(&resp.body)).into(),
+                    }
+                };
+                serde_json::json!({
+                    "status": resp.status,
+                    "headers": headers,
+                    "body": body,
+                })
+            }
+            Err(err) => serde_json::json!({ "error": redact::text(&format!("{err}")) }),
+        };
+        let dump = serde_json::json!({
+            "request": request,
+            "response": response,
+            "latency_ms": self.latency.as_millis() as u64,
+        });
+
+        std::fs::create_dir_all(dir)?;
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+        let count = DUMP_COUNT.fetch_add(1, Ordering::SeqCst);
+        let fname = dir.join(format!(
+            "{}-{}-{:03}-{}.json",
+            now.as_millis(),
+            std::process::id(),
+            count,
+            self.method
+        ));
+        let mut fp = std::fs::File::create(fname)?;
+        user_only_perm(&mut fp)?;
+        fp.write_all(serde_json::to_string_pretty(&dump)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl Client {
+    /// Create client that uses the given API token.
+    ///
+    /// If `api_token` is None, then the `REROBOTS_API_TOKEN` environment
+    /// variable is used, if it is defined.
+    pub fn new(api_token: Option<String>) -> Client {
+        Client {
+            api_token,
+            origin: default_origin(),
+            trace: false,
+            dump_dir: None,
+        }
+    }
+
+    /// Print method, URL, status, latency, and request ID of every request
+    /// to stderr.
+    pub fn with_trace(mut self, trace: bool) -> Client {
+        self.trace = trace;
+        self
+    }
+
+    /// Write every request and response to a file in `dir`, after removing
+    /// secrets like API tokens.
+    pub fn with_dump_dir<P: Into<std::path::PathBuf>>(mut self, dir: P) -> Client {
+        self.dump_dir = Some(dir.into());
+        self
+    }
+
+    /// Use a different origin for API requests, e.g., `http://127.0.0.1:8080`
+    pub fn with_origin<S: ToString>(mut self, origin: S) -> Client {
+        self.origin = origin.to_string().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    pub fn api_token(&self) -> Option<String> {
+        self.api_token.clone()
+    }
+
+    pub(crate) fn authorization(&self) -> Option<String> {
+        match &self.api_token {
+            Some(tok) => Some(format!("Bearer {tok}")),
+            None => std::env::var_os("REROBOTS_API_TOKEN").map(|tok| {
+                format!(
+                    "Bearer {}",
+                    tok.into_string()
+                        .expect("REROBOTS_API_TOKEN variable should be valid string")
+                )
+            }),
+        }
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&serde_json::Value>,
+        timeout: std::time::Duration,
+    ) -> Result<(u16, Option<serde_json::Value>), Box<dyn std::error::Error>> {
+        let url = format!("{}{}", self.origin, path);
+        let method = awc::http::Method::from_bytes(method.as_bytes())?;
+        let authheader = self.authorization();
+        let has_authheader = authheader.is_some();
+
+        let start = std::time::Instant::now();
+        let sys = System::new();
+        let result = actix::SystemRunner::block_on(&sys, async {
+            let client = awc::Client::builder().timeout(timeout);
+            let client = match authheader {
+                Some(hv) => client.add_default_header(("Authorization", hv)),
+                None => client,
+            }
+            .finish();
+            debug!("{} {}", method, path);
+            let req = client.request(method.clone(), url.as_str());
+            let mut resp = match body {
+                Some(b) => req.send_json(b).await?,
+                None => req.send().await?,
+            };
+            let status = resp.status().as_u16();
+            let headers = resp
+                .headers()
+                .iter()
+                .map(|(k, v)| {
+                    (
+                        k.as_str().to_string(),
+                        String::from_utf8_lossy(v.as_bytes()).to_string(),
+                    )
+                })
+                .collect();
+            let body = resp.body().limit(BODY_LIMIT).await?;
+            Ok::<_, Box<dyn std::error::Error>>(RawResponse {
+                status,
+                headers,
+                body: body.to_vec(),
+            })
+        });
+
+        let exchange = Exchange {
+            method: &method,
+            url: &url,
+            authorization: has_authheader,
+            body,
+            result: &result,
+            latency: start.elapsed(),
+        };
+        if self.trace {
+            exchange.trace();
+        }
+        if let Some(dir) = &self.dump_dir {
+            if let Err(err) = exchange.dump(dir) {
+                warn!("failed to write HTTP dump in {}: {}", dir.display(), err);
+            }
+        }
+        let RawResponse {
+            status,
+            body: resp_body,
+            ..
+        } = result?;
+
+        let resp_body: Option<serde_json::Value> = if resp_body.is_empty() {
+            None
+        } else {
+            let payload = serde_json::from_slice(resp_body.as_ref())?;
+            debug!(
+                "resp to {} {}: {}",
+                method,
+                path,
+                serde_json::to_string(&redact::json(&payload))?
+            );
+            Some(payload)
+        };
+
+        if let Some(record_path) = std::env::var_os("REROBOTS_RECORD") {
+            let exchange = fixture::Exchange {
+                request: fixture::Request {
+                    method: method.to_string(),
+                    path: path.to_string(),
+                    body: body.cloned(),
+                },
+                response: fixture::Response {
+                    status,
+                    body: resp_body.clone(),
+                },
+            };
+            fixture::append(std::path::Path::new(&record_path), exchange)?;
+        }
+
+        Ok((status, resp_body))
+    }
+
+    /// Send request, and return JSON payload of response if the status is 200.
+    ///
+    /// `not_found` is the error message if the status is 404. If it is None,
+    /// then 404 is treated like other unexpected statuses.
+    fn call(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&serde_json::Value>,
+        not_found: Option<&str>,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        self.call_with_timeout(
+            method,
+            path,
+            body,
+            not_found,
+            std::time::Duration::new(5, 0),
+        )
+    }
+
+    fn call_with_timeout(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&serde_json::Value>,
+        not_found: Option<&str>,
+        timeout: std::time::Duration,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let (status, payload) = self.send(method, path, body, timeout)?;
+        Client::interpret(status, payload, not_found)
+    }
+
+    /// Result of call, given response status and payload
+    fn interpret(
+        status: u16,
+        payload: Option<serde_json::Value>,
+        not_found: Option<&str>,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        if status == 200 {
+            Ok(payload.unwrap_or(serde_json::Value::Null))
+        } else if let (404, Some(msg)) = (status, not_found) {
+            ClientError::from_status(status, msg)
+        } else if status == 401 {
+            ClientError::from_status(
+                status,
+                "API token was not accepted (server indicated error: 401)",
+            )
+        } else if status == 400 {
+            match payload {
+                Some(payload) => ClientError::from_status(status, parse_error_message(&payload)),
+                None => ClientError::from_status(status, "server indicated error: 400"),
+            }
+        } else {
+            ClientError::from_status(status, format!("server indicated error: {status}"))
+        }
+    }
+
+    /// Search for deployments that match the given `query`.
+    ///
+    /// Performs full text search. If `query == None`, then match all deployments.
+    /// Optionally limit results to deployments with workspace type in `types`.
+    pub fn search(
+        &self,
+        query: Option<&str>,
+        types: Option<&Vec<&str>>,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let mut path = "/deployments?info=t".to_string();
+        if let Some(q) = query {
+            path.push_str(format!("&q={q}").as_str());
+        }
+        if let Some(t) = types {
+            path.push_str(format!("&types={}", t.join(",")).as_str());
+        }
+        self.call("GET", &path, None, None)
+    }
+
+    /// Get list of instances.
+    pub fn instances(
+        &self,
+        include_terminated: bool,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let path = if include_terminated {
+            "/instances?include_terminated"
+        } else {
+            "/instances"
+        };
+        self.call("GET", path, None, None)
+    }
+
+    /// Resolve instance ID.
+    ///
+    /// If `instance_id` is None, then select the current (INIT or READY)
+    /// instance. If there is more than one, an error is returned.
+    pub fn select_instance<S: ToString>(
+        &self,
+        instance_id: Option<S>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        match instance_id {
+            Some(inid) => Ok(inid.to_string()),
+            None => {
+                let payload = self.instances(false)?;
+                let instances = payload["workspace_instances"]
+                    .as_array()
+                    .ok_or("workspace_instances should be array")?;
+                if instances.is_empty() {
+                    ClientError::newbox("no active instances")
+                } else if instances.len() > 1 {
+                    ClientError::newbox("ambiguous command because more than one active instance")
+                } else {
+                    Ok(instances[0]
+                        .as_str()
+                        .ok_or("Elements of workspace_instances should be strings")?
+                        .to_string())
+                }
+            }
+        }
+    }
+
+    /// Get details of an instance: start time, status, deployment, ...
+    ///
+    /// If `instance_id` is None, then this command applies to the
+    /// current (INIT or READY) instance. If there is more than one,
+    /// an error is returned.
+    pub fn instance_info<S: ToString>(
+        &self,
+        instance_id: Option<S>,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let instance_id = self.select_instance(instance_id)?;
+        self.call(
+            "GET",
+            &format!("/instance/{instance_id}"),
+            None,
+            Some("instance not found"),
+        )
+    }
+
+    /// Get secret key for SSH access to instance.
+    ///
+    /// This key is available only if it was generated when the instance was
+    /// launched. Else (for example, the user included a public key with the
+    /// launch request), a not-found error is returned.
+    pub fn instance_sshkey<S: ToString>(
+        &self,
+        instance_id: Option<S>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let instance_id = self.select_instance(instance_id)?;
+        let payload = self.call(
+            "GET",
+            &format!("/instance/{instance_id}/sshkey"),
+            None,
+            Some("instance not found"),
+        )?;
+        Ok(payload["key"]
+            .as_str()
+            .ok_or("key should be string")?
+            .to_string())
+    }
+
+    /// Get details of a workspace deployment: type, region, availability, ...
+    ///
+    /// If there is an API token, then rules of the deployment are included as
+    /// `cap`.
+    pub fn wdeployment_info<S: std::fmt::Display>(
+        &self,
+        wdeployment_id: S,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let path = format!("/deployment/{wdeployment_id}");
+        let mut payload = self.call("GET", &path, None, Some("workspace deployment not found"))?;
+        if self.authorization().is_some() {
+            let rules_payload = self.call(
+                "GET",
+                &format!("{path}/rules"),
+                None,
+                Some("workspace deployment not found"),
+            )?;
+            payload
+                .as_object_mut()
+                .ok_or("Response should be JSON object")?
+                .insert("cap".to_string(), rules_payload);
+        }
+        Ok(payload)
+    }
+
+    /// Start termination of an instance.
+    ///
+    /// If `instance_id` is None, then this command applies to the
+    /// current (INIT or READY) instance.
+    pub fn terminate(&self, instance_id: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let instance_id = self.select_instance(instance_id)?;
+        self.call(
+            "POST",
+            &format!("/terminate/{instance_id}"),
+            None,
+            Some("instance not found"),
+        )?;
+        Ok(())
+    }
+
+    /// Request more time for instance.
+    ///
+    /// The new expiration is in the response as `expiration`, in seconds
+    /// since the Unix epoch.
+    pub fn extend(
+        &self,
+        instance_id: Option<&str>,
+        by: std::time::Duration,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let instance_id = self.select_instance(instance_id)?;
+        self.call(
+            "POST",
+            &format!("/instance/{instance_id}/extend"),
+            Some(&serde_json::json!({ "by": by.as_secs() })),
+            Some("instance not found"),
+        )
+    }
+
+    /// Get status of add-on of instance, e.g., `{"status": "active"}`.
+    ///
+    /// If the add-on is not enabled, then None.
+    pub fn addon_status(
+        &self,
+        addon: &str,
+        instance_id: &str,
+    ) -> Result<Option<serde_json::Value>, Box<dyn std::error::Error>> {
+        let (status, payload) = self.send(
+            "GET",
+            &format!("/addon/{addon}/{instance_id}"),
+            None,
+            std::time::Duration::new(5, 0),
+        )?;
+        if status == 404 {
+            return Ok(None);
+        }
+        Client::interpret(status, payload, None).map(Some)
+    }
+
+    /// Enable add-on of instance.
+    ///
+    /// The add-on is not necessarily active when this returns; poll
+    /// [`Client::addon_status()`] or use [`crate::enable_addon_and_wait()`].
+    pub fn enable_addon(
+        &self,
+        addon: &str,
+        instance_id: &str,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        self.call(
+            "POST",
+            &format!("/addon/{addon}/{instance_id}"),
+            None,
+            Some("instance not found or add-on not supported"),
+        )
+    }
+
+    /// Disable add-on of instance.
+    pub fn disable_addon(
+        &self,
+        addon: &str,
+        instance_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.call(
+            "DELETE",
+            &format!("/addon/{addon}/{instance_id}"),
+            None,
+            Some("add-on is not enabled"),
+        )?;
+        Ok(())
+    }
+
+    /// Get image from camera of instance, which requires the `cam` add-on.
+    pub fn camera_image(
+        &self,
+        instance_id: &str,
+        camera_id: u32,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let payload = self.call(
+            "GET",
+            &format!("/addon/cam/{instance_id}/{camera_id}/img"),
+            None,
+            Some("camera not found"),
+        )?;
+        let data = payload["data"]
+            .as_str()
+            .ok_or("image data should be string")?;
+        Ok(openssl::base64::decode_block(data)?)
+    }
+
+    /// Create VPN client of instance, which requires the `vpn` add-on.
+    ///
+    /// The client configuration is in the response as `config`, and its kind,
+    /// `openvpn` or `wireguard`, as `type`.
+    pub fn create_vpn_client(
+        &self,
+        instance_id: &str,
+        name: &str,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        self.call_with_timeout(
+            "POST",
+            &format!("/addon/vpn/{instance_id}/{name}"),
+            None,
+            Some("instance not found or vpn add-on is not active"),
+            std::time::Duration::new(30, 0),
+        )
+    }
+
+    /// Revoke VPN client of instance.
+    pub fn revoke_vpn_client(
+        &self,
+        instance_id: &str,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.call(
+            "DELETE",
+            &format!("/addon/vpn/{instance_id}/{name}"),
+            None,
+            Some("VPN client not found"),
+        )?;
+        Ok(())
+    }
+
+    /// Send archive (tar+gzip) to instance, which requires the `drive` add-on.
+    ///
+    /// The archive is unpacked at `path` on the instance. Its SHA-256 checksum
+    /// is sent with it, and the checksum of the archive that was received is in
+    /// the response as `sha256`.
+    pub fn drive_push(
+        &self,
+        instance_id: &str,
+        path: &str,
+        archive: &[u8],
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let body = serde_json::json!({
+            "path": path,
+            "archive": openssl::base64::encode_block(archive),
+            "sha256": crate::drive::sha256_hex(archive),
+        });
+        self.call_with_timeout(
+            "POST",
+            &format!("/addon/drive/{instance_id}/push"),
+            Some(&body),
+            Some("instance not found or drive add-on is not active"),
+            std::time::Duration::new(300, 0),
+        )
+    }
+
+    /// Get archive (tar+gzip) of `path` on instance, which requires the `drive` add-on.
+    ///
+    /// Return the archive and its SHA-256 checksum as reported by the instance.
+    pub fn drive_pull(
+        &self,
+        instance_id: &str,
+        path: &str,
+    ) -> Result<(Vec<u8>, String), Box<dyn std::error::Error>> {
+        let payload = self.call_with_timeout(
+            "POST",
+            &format!("/addon/drive/{instance_id}/pull"),
+            Some(&serde_json::json!({ "path": path })),
+            Some("instance or path not found, or drive add-on is not active"),
+            std::time::Duration::new(300, 0),
+        )?;
+        let archive = payload["archive"]
+            .as_str()
+            .ok_or("archive should be string")?;
+        let checksum = payload["sha256"]
+            .as_str()
+            .ok_or("checksum should be string")?;
+        Ok((
+            openssl::base64::decode_block(archive)?,
+            checksum.to_string(),
+        ))
+    }
+
+    /// Reserve workspace deployment, or any of the given type.
+    ///
+    /// The reservation is fulfilled, i.e., an instance is launched, when the
+    /// workspace deployment becomes available.
+    pub fn reserve(
+        &self,
+        wdid_or_wtype: &str,
+        public_key: Option<String>,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let body = match public_key {
+            Some(pk) => serde_json::json!({ "sshkey": pk }),
+            None => serde_json::json!({}),
+        };
+        self.reserve_with(wdid_or_wtype, &body)
+    }
+
+    /// Reserve with launch options, which are applied when the reservation is fulfilled.
+    pub fn reserve_with(
+        &self,
+        wdid_or_wtype: &str,
+        options: &serde_json::Value,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let body = match options.as_object() {
+            Some(o) if !o.is_empty() => Some(options),
+            _ => None,
+        };
+        self.call_with_timeout(
+            "POST",
+            &format!("/reserve/{wdid_or_wtype}"),
+            body,
+            None,
+            std::time::Duration::new(10, 0),
+        )
+    }
+
+    /// List reservations of this user that are not yet fulfilled.
+    pub fn reservations(&self) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        self.call("GET", "/reservations", None, None)
+    }
+
+    pub fn reservation_info(
+        &self,
+        reservation_id: &str,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        self.call(
+            "GET",
+            &format!("/reservation/{reservation_id}"),
+            None,
+            Some("reservation not found"),
+        )
+    }
+
+    pub fn cancel_reservation(
+        &self,
+        reservation_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.call(
+            "DELETE",
+            &format!("/reservation/{reservation_id}"),
+            None,
+            Some("reservation not found"),
+        )?;
+        Ok(())
+    }
+
+    /// Get public key (PEM) with which API tokens are signed.
+    pub fn public_key(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let payload = self.call("GET", "/publickey", None, None)?;
+        match payload["public_key"].as_str() {
+            Some(k) => Ok(k.to_string()),
+            None => ClientError::newbox("response does not include public key"),
+        }
+    }
+
+    /// List API tokens of this user.
+    pub fn tokens(&self) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        self.call("GET", "/tokens", None, None)
+    }
+
+    /// Create API token.
+    ///
+    /// If `expiration` (Unix time) is None, then the token does not expire. If
+    /// `scopes` is empty, then the token is not restricted to any scopes.
+    pub fn create_token(
+        &self,
+        expiration: Option<u64>,
+        scopes: &[&str],
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let mut body = serde_json::Map::new();
+        if let Some(exp) = expiration {
+            body.insert("expiration".into(), exp.into());
+        }
+        if !scopes.is_empty() {
+            body.insert("scopes".into(), scopes.into());
+        }
+        self.call(
+            "POST",
+            "/tokens",
+            Some(&serde_json::Value::Object(body)),
+            None,
+        )
+    }
+
+    /// Revoke API token.
+    pub fn revoke_token(&self, token_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.call(
+            "DELETE",
+            &format!("/token/{token_id}"),
+            None,
+            Some("token not found"),
+        )?;
+        Ok(())
+    }
+
+    /// Request new instance from the given deployment or workspace type.
+    pub fn launch(
+        &self,
+        wdid_or_wtype: &str,
+        public_key: Option<String>,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let mut body = serde_json::Map::new();
+        if let Some(pk) = public_key {
+            body.insert("sshkey".into(), pk.into());
+        }
+        self.launch_with(wdid_or_wtype, &serde_json::Value::Object(body))
+    }
+
+    /// Launch instance with options, e.g., as from [`crate::spec::LaunchSpec::request_body`].
+    pub fn launch_with(
+        &self,
+        wdid_or_wtype: &str,
+        options: &serde_json::Value,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let body = match options.as_object() {
+            Some(o) if !o.is_empty() => Some(options),
+            _ => None,
+        };
+        self.call_with_timeout(
+            "POST",
+            &format!("/new/{wdid_or_wtype}"),
+            body,
+            None,
+            std::time::Duration::new(10, 0),
+        )
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Command shell on instance host, via the `cmdsh` add-on
+//!
+//! The add-on provides a WebSocket, of which the URL is `url` in the add-on
+//! status, or else `/addon/cmdsh/{instance_id}/new` at the API origin.
+//! The first message from the client is JSON text that selects the mode:
+//!
+//! * `{"mode": "pty", "cols": 80, "rows": 24, "term": "xterm"}` for an
+//!   interactive shell, or
+//! * `{"mode": "exec", "command": "ls -l"}` to run one command.
+//!
+//! Afterward, input and output are binary messages. Other messages are JSON
+//! text: `{"resize": {"cols": 100, "rows": 40}}` and `{"eof": true}` from the
+//! client, and `{"exit": 0}` from the host when the shell or command exits.
+
+use std::io::{prelude::*, IsTerminal};
+
+use actix::System;
+use awc::ws;
+use futures_util::{SinkExt, StreamExt};
+
+use crate::client::Client;
+use crate::tty;
+
+pub enum Mode<'a> {
+    /// Interactive shell with a pseudoterminal
+    Interactive,
+
+    /// Run the command and exit
+    Command(&'a str),
+}
+
+/// URL of the WebSocket, given the add-on status
+pub fn websocket_url(client: &Client, instance_id: &str, status: &serde_json::Value) -> String {
+    if let Some(url) = status["url"].as_str() {
+        return url.to_string();
+    }
+    let origin = client.origin();
+    let origin = if let Some(rest) = origin.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = origin.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        origin.to_string()
+    };
+    format!("{origin}/addon/cmdsh/{instance_id}/new")
+}
+
+fn text(message: serde_json::Value) -> ws::Message {
+    ws::Message::Text(message.to_string().into())
+}
+
+/// Attach to shell at the WebSocket `url`, and return the exit code.
+///
+/// Input is read from stdin, and output is written to stdout. In interactive
+/// mode, stdin is put in raw mode if it is a terminal, and changes in the size
+/// of the terminal are sent to the host.
+pub fn attach(client: &Client, url: &str, mode: Mode) -> Result<i32, Box<dyn std::error::Error>> {
+    let first = match mode {
+        Mode::Interactive => {
+            let (cols, rows) = tty::size().unwrap_or((80, 24));
+            let term = std::env::var("TERM").unwrap_or_else(|_| "xterm".into());
+            serde_json::json!({"mode": "pty", "cols": cols, "rows": rows, "term": term})
+        }
+        Mode::Command(command) => serde_json::json!({"mode": "exec", "command": command}),
+    };
+    let interactive = matches!(mode, Mode::Interactive);
+
+    let mut input_rx = tty::forward_stdin();
+
+    let authorization = client.authorization();
+    let sys = System::new();
+    actix::SystemRunner::block_on(&sys, async move {
+        let mut request = awc::Client::builder()
+            .max_http_version(awc::http::Version::HTTP_11)
+            .finish()
+            .ws(url);
+        if let Some(hv) = authorization {
+            request = request.set_header("Authorization", hv);
+        }
+        debug!("connecting to {}", crate::redact::text(url));
+        let (_, mut socket) = request
+            .connect()
+            .await
+            .map_err(|err| format!("cannot connect to shell: {err}"))?;
+        socket.send(text(first)).await?;
+
+        let _raw_mode = if interactive && std::io::stdin().is_terminal() {
+            Some(tty::RawMode::new())
+        } else {
+            None
+        };
+        let mut size = tty::size();
+        let mut resize_check = tokio::time::interval(std::time::Duration::from_millis(250));
+        let mut stdout = std::io::stdout();
+        let mut input_open = true;
+
+        loop {
+            tokio::select! {
+                frame = socket.next() => {
+                    let frame = match frame {
+                        Some(f) => f?,
+                        None => return Err("connection to shell closed without exit status".into()),
+                    };
+                    match frame {
+                        ws::Frame::Binary(data) => {
+                            stdout.write_all(&data)?;
+                            stdout.flush()?;
+                        }
+                        ws::Frame::Text(data) => {
+                            let message: serde_json::Value = serde_json::from_slice(&data)?;
+                            if let Some(code) = message["exit"].as_i64() {
+                                let _ = socket.send(ws::Message::Close(None)).await;
+                                return Ok(code as i32);
+                            }
+                        }
+                        ws::Frame::Ping(data) => socket.send(ws::Message::Pong(data)).await?,
+                        ws::Frame::Close(_) => {
+                            return Err("connection to shell closed without exit status".into())
+                        }
+                        _ => {}
+                    }
+                }
+                input = input_rx.recv(), if input_open => {
+                    match input {
+                        Some(data) => socket.send(ws::Message::Binary(data.into())).await?,
+                        None => {
+                            input_open = false;
+                            socket.send(text(serde_json::json!({"eof": true}))).await?;
+                        }
+                    }
+                }
+                _ = resize_check.tick(), if interactive => {
+                    let current = tty::size();
+                    if current != size {
+                        size = current;
+                        if let Some((cols, rows)) = size {
+                            socket
+                                .send(text(serde_json::json!({"resize": {"cols": cols, "rows": rows}})))
+                                .await?;
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::websocket_url;
+    use crate::Client;
+
+    #[test]
+    fn url_from_origin() {
+        let client = Client::new(None).with_origin("https://api.rerobots.net");
+        let instance_id = "c9b9c1d6-69c9-4b3e-9e2e-d5d2ea8bd3f1";
+        assert_eq!(
+            websocket_url(
+                &client,
+                instance_id,
+                &serde_json::json!({"status": "active"})
+            ),
+            format!("wss://api.rerobots.net/addon/cmdsh/{instance_id}/new")
+        );
+        let status = serde_json::json!({"status": "active", "url": "wss://cmdsh.rerobots.net/x"});
+        assert_eq!(
+            websocket_url(&client, instance_id, &status),
+            "wss://cmdsh.rerobots.net/x"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Configuration file and profiles
+//!
+//! The configuration file is YAML, at `$XDG_CONFIG_HOME/rerobots/config.yaml`
+//! by default, or at the path given by the environment variable
+//! `REROBOTS_CONFIG`. It has a map of named profiles, e.g.,
+//!
+//! ```yaml
+//! default_profile: ci
+//! profiles:
+//!   ci:
+//!     log_file: /var/log/rerobots/cli.log
+//!     log_format: json
+//!     credential_backend: file
+//! ```
+//!
+//! The profile is selected by `--profile`, else the environment variable
+//! `REROBOTS_PROFILE`, else `default_profile`, else the profile named
+//! `default` if there is one.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Settings that apply when a profile is active
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// file in which to write logs instead of stderr
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<PathBuf>,
+
+    /// `text` or `json`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_format: Option<String>,
+
+    /// plaintext file containing API token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_token_file: Option<PathBuf>,
+
+    /// where API tokens are stored: `secret-service` or `file`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_backend: Option<String>,
+
+    /// warn if the API token expires within this duration, e.g., `7d`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_expiry_warning: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let home = std::env::var_os("USERPROFILE");
+    #[cfg(not(windows))]
+    let home = std::env::var_os("HOME");
+    home.filter(|h| !h.is_empty()).map(PathBuf::from)
+}
+
+/// Directory for configuration, e.g., `~/.config/rerobots`
+pub fn config_dir() -> Option<PathBuf> {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(d) if !d.is_empty() => Some(PathBuf::from(d).join("rerobots")),
+        _ => home_dir().map(|h| h.join(".config").join("rerobots")),
+    }
+}
+
+/// Directory for data managed by `rerobots`, e.g., `~/.local/share/rerobots`
+pub fn data_dir() -> Option<PathBuf> {
+    match std::env::var_os("XDG_DATA_HOME") {
+        Some(d) if !d.is_empty() => Some(PathBuf::from(d).join("rerobots")),
+        _ => home_dir().map(|h| h.join(".local").join("share").join("rerobots")),
+    }
+}
+
+/// Directory for cached data, e.g., `~/.cache/rerobots`
+pub fn cache_dir() -> Option<PathBuf> {
+    match std::env::var_os("XDG_CACHE_HOME") {
+        Some(d) if !d.is_empty() => Some(PathBuf::from(d).join("rerobots")),
+        _ => home_dir().map(|h| h.join(".cache").join("rerobots")),
+    }
+}
+
+/// Path of the configuration file, which might not exist
+pub fn config_path() -> Option<PathBuf> {
+    match std::env::var_os("REROBOTS_CONFIG") {
+        Some(p) if !p.is_empty() => Some(PathBuf::from(p)),
+        _ => config_dir().map(|d| d.join("config.yaml")),
+    }
+}
+
+impl Config {
+    /// Read configuration file. If there is none, then the configuration is
+    /// empty.
+    pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
+        match config_path() {
+            Some(path) if path.exists() => Config::load_from(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    pub fn load_from(path: &std::path::Path) -> Result<Config, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        if text.trim().is_empty() {
+            return Ok(Config::default());
+        }
+        match serde_yaml::from_str(&text) {
+            Ok(c) => Ok(c),
+            Err(err) => Err(format!("{}: {}", path.display(), err).into()),
+        }
+    }
+
+    /// Name of the profile to use, given `--profile` (if any)
+    pub fn active_profile_name(&self, given: Option<&str>) -> Option<String> {
+        if let Some(name) = given {
+            return Some(name.to_string());
+        }
+        if let Ok(name) = std::env::var("REROBOTS_PROFILE") {
+            if !name.is_empty() {
+                return Some(name);
+            }
+        }
+        if let Some(name) = &self.default_profile {
+            return Some(name.clone());
+        }
+        if self.profiles.contains_key("default") {
+            return Some("default".into());
+        }
+        None
+    }
+
+    /// Get profile by name. An error is returned if it is not defined.
+    pub fn profile(&self, name: &str) -> Result<Profile, Box<dyn std::error::Error>> {
+        match self.profiles.get(name) {
+            Some(p) => Ok(p.clone()),
+            None => Err(format!("profile not found: {name}").into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn parse_profiles() -> TestResult {
+        let config: Config = serde_yaml::from_str(
+            "
+default_profile: ci
+profiles:
+  ci:
+    log_file: /tmp/rerobots.log
+    log_format: json
+  laptop: {}
+",
+        )?;
+        assert_eq!(config.default_profile.as_deref(), Some("ci"));
+        let profile = config.profile("ci")?;
+        assert_eq!(profile.log_format.as_deref(), Some("json"));
+        assert_eq!(
+            profile.log_file,
+            Some(std::path::PathBuf::from("/tmp/rerobots.log"))
+        );
+        assert_eq!(config.profile("laptop")?, super::Profile::default());
+        assert!(config.profile("other").is_err());
+        assert_eq!(
+            config.active_profile_name(Some("laptop")).as_deref(),
+            Some("laptop")
+        );
+        Ok(())
+    }
+}
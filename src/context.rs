@@ -0,0 +1,104 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::client::Client;
+use crate::config::Profile;
+use crate::output::PrintingFormat;
+
+/// Answer to use for questions that would otherwise be asked interactively
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DefaultConfirmAnswer {
+    Yes,
+    No,
+    None,
+}
+
+/// Where the API token came from
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenSource {
+    /// file given with `-t`
+    Flag(std::path::PathBuf),
+
+    /// environment variable `REROBOTS_API_TOKEN`
+    Environment,
+
+    /// file given by `api_token_file` of the named profile
+    Profile(String, std::path::PathBuf),
+
+    /// credential store (e.g., `secret-service`) for the named profile
+    Stored(String, &'static str),
+}
+
+impl std::fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenSource::Flag(path) => write!(f, "file {} (-t)", path.display()),
+            TokenSource::Environment => write!(f, "environment variable REROBOTS_API_TOKEN"),
+            TokenSource::Profile(name, path) => {
+                write!(f, "file {} (profile {name})", path.display())
+            }
+            TokenSource::Stored(name, backend) => write!(f, "{backend} (profile {name})"),
+        }
+    }
+}
+
+/// Configuration shared by all operations
+///
+/// The `rerobots` program builds this from global options, e.g., `-t` and
+/// `--format`, before running a subcommand.
+pub struct Context {
+    /// API token, if given
+    pub api_token: Option<String>,
+
+    /// where `api_token` came from
+    pub token_source: Option<TokenSource>,
+
+    /// output formatting
+    pub pformat: PrintingFormat,
+
+    /// answer to use instead of interactive prompts
+    pub default_confirm: DefaultConfirmAnswer,
+
+    /// client for the rerobots API, using `api_token`
+    pub client: Client,
+
+    /// name of the active profile, if any
+    pub profile_name: Option<String>,
+
+    /// settings of the active profile
+    pub profile: Profile,
+}
+
+impl Context {
+    /// Create context with default output format and interactive prompts.
+    ///
+    /// If `api_token` is None, then the `REROBOTS_API_TOKEN` environment
+    /// variable is used, if it is defined.
+    pub fn new(api_token: Option<String>) -> Context {
+        Context {
+            client: Client::new(api_token.clone()),
+            api_token,
+            token_source: None,
+            pformat: PrintingFormat::Default,
+            default_confirm: DefaultConfirmAnswer::None,
+            profile_name: None,
+            profile: Profile::default(),
+        }
+    }
+
+    /// Name under which API tokens of the active profile are stored
+    pub fn credential_name(&self) -> &str {
+        self.profile_name.as_deref().unwrap_or("default")
+    }
+}
@@ -0,0 +1,421 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage of API tokens outside of plaintext files
+//!
+//! Tokens are stored per profile. On Linux, the default backend is the
+//! Secret Service (e.g., GNOME Keyring or KWallet) via D-Bus. Elsewhere, or if
+//! no Secret Service is available, tokens are stored in a file that is
+//! encrypted with a passphrase.
+
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use openssl::base64;
+use openssl::symm::Cipher;
+use serde::{Deserialize, Serialize};
+
+use crate::files::user_only_perm;
+
+/// Backend for storing API tokens
+pub trait CredentialStore {
+    /// Name of the backend, as in the `credential_backend` profile setting
+    fn name(&self) -> &'static str;
+
+    /// Get token stored for the profile, if any.
+    fn get(&self, profile: &str) -> Result<Option<String>, Box<dyn std::error::Error>>;
+
+    /// Store token for the profile, replacing any that already exists.
+    fn set(&self, profile: &str, token: &str) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Delete token stored for the profile. Returns whether there was one.
+    fn delete(&self, profile: &str) -> Result<bool, Box<dyn std::error::Error>>;
+}
+
+/// Open backend by name, or the default backend if `backend` is None.
+///
+/// Names are `secret-service` and `file`. The environment variable
+/// `REROBOTS_CREDENTIAL_BACKEND` takes precedence over `backend`.
+pub fn open(backend: Option<&str>) -> Result<Box<dyn CredentialStore>, Box<dyn std::error::Error>> {
+    let from_env = std::env::var("REROBOTS_CREDENTIAL_BACKEND").ok();
+    let backend = match from_env.as_deref() {
+        Some(b) if !b.is_empty() => Some(b),
+        _ => backend,
+    };
+    match backend {
+        Some("file") => Ok(Box::new(EncryptedFile::default_path()?)),
+        #[cfg(target_os = "linux")]
+        Some("secret-service") => Ok(Box::new(SecretService::connect()?)),
+        Some(b) => Err(format!("unsupported credential backend: {b}").into()),
+        None => {
+            #[cfg(target_os = "linux")]
+            if std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some() {
+                match SecretService::connect() {
+                    Ok(ss) => return Ok(Box::new(ss)),
+                    Err(err) => {
+                        info!("Secret Service is not available: {}", err);
+                    }
+                }
+            }
+            Ok(Box::new(EncryptedFile::default_path()?))
+        }
+    }
+}
+
+/// Describe problem with permissions of a file that contains secrets, if any.
+///
+/// On Unix, the file should not be accessible by group or other users.
+pub fn check_permissions(path: &std::path::Path) -> Option<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(path).ok()?.permissions().mode();
+        if mode & 0o077 != 0 {
+            return Some(format!(
+                "{} is accessible by other users (mode {:o}); try `chmod 600 {}`",
+                path.display(),
+                mode & 0o777,
+                path.display()
+            ));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    None
+}
+
+const PBKDF2_ITERATIONS: usize = 210_000;
+
+#[derive(Default, Serialize, Deserialize)]
+struct EncryptedFileContents {
+    entries: BTreeMap<String, EncryptedEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedEntry {
+    kdf: String,
+    iterations: usize,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+    tag: String,
+}
+
+/// Tokens in a file encrypted with AES-256-GCM
+///
+/// The key is derived from a passphrase with PBKDF2-HMAC-SHA256. The passphrase
+/// is read from the environment variable `REROBOTS_CREDENTIALS_PASSPHRASE`, or
+/// else it is prompted for.
+pub struct EncryptedFile {
+    path: PathBuf,
+    passphrase: std::cell::RefCell<Option<String>>,
+}
+
+impl EncryptedFile {
+    pub fn new<P: Into<PathBuf>>(path: P) -> EncryptedFile {
+        EncryptedFile {
+            path: path.into(),
+            passphrase: std::cell::RefCell::new(None),
+        }
+    }
+
+    /// File `credentials` in the data directory, e.g., `~/.local/share/rerobots`
+    pub fn default_path() -> Result<EncryptedFile, Box<dyn std::error::Error>> {
+        let dir = crate::config::data_dir().ok_or("cannot find home directory")?;
+        Ok(EncryptedFile::new(dir.join("credentials")))
+    }
+
+    fn passphrase(&self) -> Result<String, Box<dyn std::error::Error>> {
+        if let Some(p) = self.passphrase.borrow().as_ref() {
+            return Ok(p.clone());
+        }
+        let passphrase = match std::env::var("REROBOTS_CREDENTIALS_PASSPHRASE") {
+            Ok(p) => p,
+            Err(_) => {
+                if !std::io::stdin().is_terminal() {
+                    return Err("passphrase for credentials file is required; define REROBOTS_CREDENTIALS_PASSPHRASE".into());
+                }
+                crate::tty::read_hidden_line(&format!("Passphrase for {}: ", self.path.display()))?
+            }
+        };
+        if passphrase.is_empty() {
+            return Err("passphrase for credentials file must not be empty".into());
+        }
+        *self.passphrase.borrow_mut() = Some(passphrase.clone());
+        Ok(passphrase)
+    }
+
+    fn derive_key(
+        &self,
+        salt: &[u8],
+        iterations: usize,
+    ) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+        let mut key = [0u8; 32];
+        openssl::pkcs5::pbkdf2_hmac(
+            self.passphrase()?.as_bytes(),
+            salt,
+            iterations,
+            openssl::hash::MessageDigest::sha256(),
+            &mut key,
+        )?;
+        Ok(key)
+    }
+
+    fn load(&self) -> Result<EncryptedFileContents, Box<dyn std::error::Error>> {
+        if !self.path.exists() {
+            return Ok(EncryptedFileContents::default());
+        }
+        if let Some(problem) = check_permissions(&self.path) {
+            eprintln!("warning: {problem}");
+        }
+        let text = std::fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    fn save(&self, contents: &EncryptedFileContents) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut fp = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        user_only_perm(&mut fp)?;
+        fp.write_all(serde_json::to_string_pretty(contents)?.as_bytes())?;
+        fp.sync_all()?;
+        Ok(())
+    }
+
+    fn encrypt(
+        &self,
+        profile: &str,
+        token: &str,
+    ) -> Result<EncryptedEntry, Box<dyn std::error::Error>> {
+        let mut salt = [0u8; 16];
+        openssl::rand::rand_bytes(&mut salt)?;
+        let mut nonce = [0u8; 12];
+        openssl::rand::rand_bytes(&mut nonce)?;
+        let key = self.derive_key(&salt, PBKDF2_ITERATIONS)?;
+        let mut tag = [0u8; 16];
+        let ciphertext = openssl::symm::encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &key,
+            Some(&nonce),
+            profile.as_bytes(),
+            token.as_bytes(),
+            &mut tag,
+        )?;
+        Ok(EncryptedEntry {
+            kdf: "pbkdf2-sha256".into(),
+            iterations: PBKDF2_ITERATIONS,
+            salt: base64::encode_block(&salt),
+            nonce: base64::encode_block(&nonce),
+            ciphertext: base64::encode_block(&ciphertext),
+            tag: base64::encode_block(&tag),
+        })
+    }
+
+    fn decrypt(
+        &self,
+        profile: &str,
+        entry: &EncryptedEntry,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        if entry.kdf != "pbkdf2-sha256" {
+            return Err(format!("unsupported key derivation: {}", entry.kdf).into());
+        }
+        let key = self.derive_key(&base64::decode_block(&entry.salt)?, entry.iterations)?;
+        let plaintext = openssl::symm::decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &key,
+            Some(&base64::decode_block(&entry.nonce)?),
+            profile.as_bytes(),
+            &base64::decode_block(&entry.ciphertext)?,
+            &base64::decode_block(&entry.tag)?,
+        )
+        .map_err(|_| "cannot decrypt stored token; is the passphrase correct?")?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+}
+
+impl CredentialStore for EncryptedFile {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn get(&self, profile: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let contents = self.load()?;
+        match contents.entries.get(profile) {
+            Some(entry) => Ok(Some(self.decrypt(profile, entry)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn set(&self, profile: &str, token: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut contents = self.load()?;
+        let entry = self.encrypt(profile, token)?;
+        contents.entries.insert(profile.to_string(), entry);
+        self.save(&contents)
+    }
+
+    fn delete(&self, profile: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut contents = self.load()?;
+        if contents.entries.remove(profile).is_none() {
+            return Ok(false);
+        }
+        self.save(&contents)?;
+        Ok(true)
+    }
+}
+
+/// Tokens in the Secret Service, via D-Bus
+#[cfg(target_os = "linux")]
+pub struct SecretService {
+    ss: secret_service::blocking::SecretService<'static>,
+}
+
+#[cfg(target_os = "linux")]
+impl SecretService {
+    /// Connect to the Secret Service on the session bus.
+    ///
+    /// An encrypted session is preferred, but if the service does not support
+    /// it, then secrets are transferred in plain text over the session bus.
+    pub fn connect() -> Result<SecretService, Box<dyn std::error::Error>> {
+        use secret_service::EncryptionType;
+        let ss = match secret_service::blocking::SecretService::connect(EncryptionType::Dh) {
+            Ok(ss) => ss,
+            Err(err) => {
+                debug!("encrypted session with Secret Service failed: {}", err);
+                secret_service::blocking::SecretService::connect(EncryptionType::Plain)?
+            }
+        };
+        Ok(SecretService { ss })
+    }
+
+    fn attributes(profile: &str) -> std::collections::HashMap<&str, &str> {
+        std::collections::HashMap::from([("application", "rerobots"), ("profile", profile)])
+    }
+
+    fn find(
+        &self,
+        profile: &str,
+    ) -> Result<Vec<secret_service::blocking::Item<'_>>, Box<dyn std::error::Error>> {
+        let result = self.ss.search_items(SecretService::attributes(profile))?;
+        if !result.locked.is_empty() {
+            let locked: Vec<_> = result.locked.iter().collect();
+            self.ss.unlock_all(&locked)?;
+        }
+        Ok(result.unlocked.into_iter().chain(result.locked).collect())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl CredentialStore for SecretService {
+    fn name(&self) -> &'static str {
+        "secret-service"
+    }
+
+    fn get(&self, profile: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        match self.find(profile)?.first() {
+            Some(item) => Ok(Some(String::from_utf8(item.get_secret()?)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn set(&self, profile: &str, token: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let collection = self.ss.get_default_collection()?;
+        collection.ensure_unlocked()?;
+        collection.create_item(
+            &format!("rerobots API token ({profile})"),
+            SecretService::attributes(profile),
+            token.as_bytes(),
+            true,
+            "text/plain",
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, profile: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let items = self.find(profile)?;
+        for item in items.iter() {
+            item.delete()?;
+        }
+        Ok(!items.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CredentialStore, EncryptedFile};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    fn store_with_passphrase(name: &str, passphrase: &str) -> EncryptedFile {
+        let store = EncryptedFile::new(
+            std::env::temp_dir().join(format!("rerobots-{}-{name}", std::process::id())),
+        );
+        *store.passphrase.borrow_mut() = Some(passphrase.into());
+        store
+    }
+
+    #[test]
+    fn encrypted_file() -> TestResult {
+        let store = store_with_passphrase("credentials", "correct horse");
+        assert_eq!(store.get("default")?, None);
+        store.set("default", "abc.def.ghi")?;
+        store.set("ci", "jkl.mno.pqr")?;
+        assert_eq!(store.get("default")?.as_deref(), Some("abc.def.ghi"));
+        assert_eq!(store.get("ci")?.as_deref(), Some("jkl.mno.pqr"));
+
+        let text = std::fs::read_to_string(&store.path)?;
+        assert!(!text.contains("abc.def.ghi"));
+        #[cfg(unix)]
+        assert_eq!(super::check_permissions(&store.path), None);
+
+        assert!(store.delete("default")?);
+        assert!(!store.delete("default")?);
+        assert_eq!(store.get("default")?, None);
+        assert_eq!(store.get("ci")?.as_deref(), Some("jkl.mno.pqr"));
+
+        std::fs::remove_file(&store.path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn wrong_passphrase() -> TestResult {
+        let store = store_with_passphrase("credentials-wrong", "correct horse");
+        store.set("default", "abc.def.ghi")?;
+        let other = store_with_passphrase("credentials-wrong", "battery staple");
+        assert!(other.get("default").is_err());
+        std::fs::remove_file(&store.path)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions() -> TestResult {
+        use std::os::unix::fs::PermissionsExt;
+        let path = std::env::temp_dir().join(format!("rerobots-{}-perm", std::process::id()));
+        std::fs::write(&path, "abc.def.ghi")?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644))?;
+        let problem = super::check_permissions(&path).ok_or("should detect problem")?;
+        assert!(problem.contains("(mode 644)"));
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        assert_eq!(super::check_permissions(&path), None);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
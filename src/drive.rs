@@ -0,0 +1,158 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Archives for copying files with the `drive` add-on
+//!
+//! Directories are packaged as tar archives compressed with gzip. When packing,
+//! files that are ignored by `.gitignore` (or `.ignore`) are skipped, as are
+//! `.git` directories.
+
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// SHA-256 digest of `data` as lowercase hexadecimal
+pub fn sha256_hex(data: &[u8]) -> String {
+    openssl::sha::sha256(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Pack the contents of directory `dir` into an archive.
+///
+/// If `respect_ignore`, then files ignored by `.gitignore` are skipped.
+/// `progress` is called with the relative path and size of each file that is
+/// added.
+pub fn pack<F: FnMut(&Path, u64)>(
+    dir: &Path,
+    respect_ignore: bool,
+    mut progress: F,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if !dir.is_dir() {
+        return Err(format!("not a directory: {}", dir.display()).into());
+    }
+    let walker = ignore::WalkBuilder::new(dir)
+        .standard_filters(respect_ignore)
+        .hidden(false)
+        .git_global(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    builder.follow_symlinks(false);
+    for entry in walker {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(dir)?;
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            builder.append_dir(relative, entry.path())?;
+        } else {
+            builder.append_path_with_name(entry.path(), relative)?;
+            progress(relative, metadata.len());
+        }
+    }
+    Ok(builder.into_inner()?.finish()?)
+}
+
+/// Unpack `archive` into directory `dir`, which is created if it does not exist.
+///
+/// `progress` is called with the relative path and size of each file that is
+/// extracted. Entries with paths outside of `dir` are rejected.
+pub fn unpack<F: FnMut(&Path, u64)>(
+    archive: &[u8],
+    dir: &Path,
+    mut progress: F,
+) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(dir)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(archive));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let size = entry.size();
+        if !entry.unpack_in(dir)? {
+            return Err(format!("unsafe path in archive: {}", path.display()).into());
+        }
+        if entry.header().entry_type().is_file() {
+            progress(&path, size);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pack, sha256_hex, unpack};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn checksum() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn pack_and_unpack() -> TestResult {
+        let base = std::env::temp_dir().join(format!("rerobots-{}-drive", std::process::id()));
+        let src = base.join("src");
+        std::fs::create_dir_all(src.join("sub"))?;
+        std::fs::create_dir_all(src.join(".git"))?;
+        std::fs::write(src.join(".gitignore"), "*.log\n")?;
+        std::fs::write(src.join("main.py"), "print('hello')\n")?;
+        std::fs::write(src.join("run.log"), "noise\n")?;
+        std::fs::write(src.join("sub").join("data.txt"), "42\n")?;
+        std::fs::write(src.join(".git").join("HEAD"), "ref: refs/heads/main\n")?;
+
+        let mut packed = vec![];
+        let archive = pack(&src, true, |path, size| {
+            packed.push((path.to_string_lossy().to_string(), size))
+        })?;
+        assert_eq!(
+            packed,
+            vec![
+                (".gitignore".into(), 6),
+                ("main.py".into(), 15),
+                ("sub/data.txt".into(), 3)
+            ]
+        );
+        let mut unpacked = vec![];
+        let dst = base.join("dst");
+        unpack(&archive, &dst, |path, _| {
+            unpacked.push(path.to_string_lossy().to_string())
+        })?;
+        assert_eq!(unpacked, vec![".gitignore", "main.py", "sub/data.txt"]);
+        assert_eq!(
+            std::fs::read_to_string(dst.join("sub").join("data.txt"))?,
+            "42\n"
+        );
+        assert!(!dst.join("run.log").exists());
+
+        let mut count = 0;
+        pack(&src, false, |_, _| count += 1)?;
+        assert_eq!(count, 4);
+
+        std::fs::remove_dir_all(&base)?;
+        Ok(())
+    }
+}
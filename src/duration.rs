@@ -0,0 +1,113 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Durations as typed on the command line, e.g., `30d` or `1h30m`
+
+use std::time::Duration;
+
+const UNITS: [(char, u64); 5] = [
+    ('w', 7 * 24 * 3600),
+    ('d', 24 * 3600),
+    ('h', 3600),
+    ('m', 60),
+    ('s', 1),
+];
+
+/// Parse duration like `30d`, `1h30m`, or `90s`.
+///
+/// Units are `w` (weeks), `d` (days), `h` (hours), `m` (minutes), and `s`
+/// (seconds). A number without unit is seconds.
+pub fn parse(given: &str) -> Result<Duration, Box<dyn std::error::Error>> {
+    let invalid = || format!("invalid duration: {given}");
+    let given_trimmed = given.trim();
+    if given_trimmed.is_empty() {
+        return Err(invalid().into());
+    }
+    if let Ok(seconds) = given_trimmed.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in given_trimmed.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let multiplier = match UNITS.iter().find(|(u, _)| *u == c) {
+            Some((_, m)) => *m,
+            None => return Err(invalid().into()),
+        };
+        if number.is_empty() {
+            return Err(invalid().into());
+        }
+        let n: u64 = number.parse().map_err(|_| invalid())?;
+        total = n
+            .checked_mul(multiplier)
+            .and_then(|x| total.checked_add(x))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(invalid().into());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// Format duration with its two largest units, e.g., `29d 23h` or `5m 0s`
+pub fn format(duration: Duration) -> String {
+    let mut remaining = duration.as_secs();
+    let mut parts = vec![];
+    for (unit, seconds) in UNITS.iter().skip(1) {
+        if parts.is_empty() && remaining < *seconds && *unit != 's' {
+            continue;
+        }
+        parts.push(format!("{}{}", remaining / seconds, unit));
+        remaining %= seconds;
+        if parts.len() == 2 {
+            break;
+        }
+    }
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{format, parse};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn parse_durations() -> TestResult {
+        assert_eq!(parse("30d")?, Duration::from_secs(30 * 24 * 3600));
+        assert_eq!(parse("1h30m")?, Duration::from_secs(5400));
+        assert_eq!(parse("2s")?, Duration::from_secs(2));
+        assert_eq!(parse("45")?, Duration::from_secs(45));
+        assert_eq!(parse("1w")?, Duration::from_secs(7 * 24 * 3600));
+        for invalid in ["", "h", "1x", "1h30", "-1h"] {
+            assert!(parse(invalid).is_err(), "{invalid} should not be parsed");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn format_durations() {
+        assert_eq!(format(Duration::from_secs(30 * 24 * 3600 - 60)), "29d 23h");
+        assert_eq!(format(Duration::from_secs(5400)), "1h 30m");
+        assert_eq!(format(Duration::from_secs(300)), "5m 0s");
+        assert_eq!(format(Duration::from_secs(7)), "7s");
+        assert_eq!(format(Duration::from_secs(0)), "0s");
+    }
+}
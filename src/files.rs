@@ -0,0 +1,73 @@
+// Copyright (C) 2021 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs::{File, OpenOptions};
+use std::io::prelude::*;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+#[cfg(unix)]
+pub(crate) fn user_only_perm(fp: &mut File) -> Result<(), Box<dyn std::error::Error>> {
+    let mut perm = fp.metadata()?.permissions();
+    perm.set_mode(0o600);
+    fp.set_permissions(perm)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn user_only_perm(fp: &mut File) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
+
+/// Write secret key to a file that only the current user can read.
+///
+/// If the file already exists, it is overwritten.
+pub fn write_secret_key(fname: &str, secret_key: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut fp = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(fname)?;
+    user_only_perm(&mut fp)?;
+    fp.write_all(secret_key.as_bytes())?;
+    fp.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_secret_key;
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn secret_key_file() -> TestResult {
+        let path = std::env::temp_dir().join(format!("rerobots-test-{}.pem", std::process::id()));
+        let fname = path
+            .to_str()
+            .ok_or("temporary path should be valid string")?;
+        write_secret_key(fname, "original")?;
+        write_secret_key(fname, "secret")?;
+        assert_eq!(std::fs::read_to_string(&path)?, "secret");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Record of API requests and responses
+//!
+//! A fixture file is a JSON array of [`Exchange`] objects. The `rerobots`
+//! program appends to such a file if the environment variable
+//! `REROBOTS_RECORD` is defined, and the file can be replayed by a stand-in
+//! server in tests. For example,
+//!
+//! ```json
+//! [
+//!   {
+//!     "request": { "method": "POST", "path": "/terminate/7f3f4ad2" },
+//!     "response": { "status": 200 }
+//!   }
+//! ]
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    pub method: String,
+
+    /// path and query, e.g., `/instances?include_terminated`
+    pub path: String,
+
+    /// JSON body, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub status: u16,
+
+    /// JSON body, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<serde_json::Value>,
+}
+
+/// Request and the response to it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    pub request: Request,
+    pub response: Response,
+}
+
+/// Read all exchanges from a fixture file.
+pub fn load(path: &std::path::Path) -> Result<Vec<Exchange>, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+/// Append exchange to a fixture file, creating the file if necessary.
+pub fn append(
+    path: &std::path::Path,
+    exchange: Exchange,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut exchanges = if path.exists() { load(path)? } else { vec![] };
+    exchanges.push(exchange);
+    std::fs::write(path, serde_json::to_string_pretty(&exchanges)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append, load, Exchange, Request, Response};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn append_and_load() -> TestResult {
+        let path =
+            std::env::temp_dir().join(format!("rerobots-fixture-{}.json", std::process::id()));
+        let first = Exchange {
+            request: Request {
+                method: "GET".into(),
+                path: "/instances".into(),
+                body: None,
+            },
+            response: Response {
+                status: 200,
+                body: Some(serde_json::json!({"workspace_instances": []})),
+            },
+        };
+        let second = Exchange {
+            request: Request {
+                method: "POST".into(),
+                path: "/terminate/7f3f4ad2".into(),
+                body: None,
+            },
+            response: Response {
+                status: 200,
+                body: None,
+            },
+        };
+        append(&path, first.clone())?;
+        append(&path, second.clone())?;
+        assert_eq!(load(&path)?, vec![first, second]);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
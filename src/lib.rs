@@ -0,0 +1,61 @@
+//! Command-line interface (CLI) for [rerobots](https://rerobots.net)
+//!
+//! Besides the `rerobots` program, this crate provides the operations on
+//! which it is built, e.g., [`launch_and_wait()`] and [`ssh_command_for()`],
+//! so that they can be reused by other tools written in Rust.
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[macro_use]
+extern crate clap;
+
+#[macro_use]
+extern crate log;
+
+pub mod agent;
+pub mod claims;
+pub mod cli;
+mod client;
+pub mod cmdsh;
+pub mod config;
+mod context;
+pub mod credentials;
+pub mod drive;
+pub mod duration;
+mod files;
+pub mod fixture;
+pub mod logging;
+#[cfg(feature = "native-ssh")]
+pub mod native_ssh;
+mod ops;
+mod output;
+pub mod redact;
+pub mod relay;
+#[cfg(unix)]
+mod signals;
+pub mod spec;
+pub mod sshkey;
+pub mod state;
+mod tty;
+pub mod vpn;
+
+pub use client::{default_origin, is_transient, Client};
+pub use context::{Context, DefaultConfirmAnswer, TokenSource};
+pub use files::write_secret_key;
+pub use ops::{
+    enable_addon_and_wait, instances_info, launch_and_wait, public_key, remaining_time,
+    ssh_command_for, wait_for_reservation, wait_until_ready, wdeployments_info, with_auto_extend,
+    AutoExtend,
+};
+pub use output::{format_timestamp, render, PrintingFormat};
@@ -12,15 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-extern crate log;
-
-#[macro_use]
-extern crate clap;
-
-extern crate serde_json;
-extern crate serde_yaml;
-
-mod cli;
+use rerobots_cli::cli;
 
 fn main() {
     rerobots::init();
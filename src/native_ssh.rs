@@ -0,0 +1,307 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Built-in SSH client, which is available with the cargo feature `native-ssh`
+//!
+//! The CLI uses it instead of the `ssh` program if that is not found, or if
+//! `--native` is given. Host keys are checked against the file `known_hosts`
+//! in the rerobots data directory: the key of a host that is not in the file is
+//! added to it, and a key that differs from the one in the file is rejected.
+
+use std::io::{prelude::*, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use actix::{System, SystemRunner};
+use russh::client;
+use russh::keys::{self, known_hosts, PrivateKeyWithHashAlg, PublicKeyOrCertificate};
+use russh::ChannelMsg;
+use russh_sftp::client::SftpSession;
+use tokio::io::AsyncWriteExt;
+
+pub use crate::cmdsh::Mode;
+use crate::tty;
+
+#[derive(Debug)]
+enum Error {
+    Ssh(russh::Error),
+    Keys(keys::Error),
+    HostKeyChanged {
+        host: String,
+        port: u16,
+        path: PathBuf,
+    },
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Ssh(err) => write!(f, "{err}"),
+            Error::Keys(err) => write!(f, "{err}"),
+            Error::HostKeyChanged { host, port, path } => write!(
+                f,
+                "host key of [{host}]:{port} differs from the one in {}; \
+                 if the instance was replaced, then remove the line of this host from that file",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl From<russh::Error> for Error {
+    fn from(err: russh::Error) -> Self {
+        Error::Ssh(err)
+    }
+}
+
+/// Path of the known hosts file, which might not exist
+pub fn known_hosts_path() -> Option<PathBuf> {
+    crate::config::data_dir().map(|d| d.join("known_hosts"))
+}
+
+struct Handler {
+    host: String,
+    port: u16,
+    known_hosts: PathBuf,
+}
+
+impl client::Handler for Handler {
+    type Error = Error;
+
+    async fn check_server_key(&mut self, key: &PublicKeyOrCertificate) -> Result<bool, Error> {
+        let key = match key {
+            PublicKeyOrCertificate::PublicKey { key, .. } => key,
+            PublicKeyOrCertificate::Certificate(_) => return Ok(false),
+        };
+        match known_hosts::check_known_hosts_path(&self.host, self.port, key, &self.known_hosts) {
+            Ok(true) => Ok(true),
+            Ok(false) => {
+                known_hosts::learn_known_hosts_path(&self.host, self.port, key, &self.known_hosts)
+                    .map_err(Error::Keys)?;
+                eprintln!(
+                    "Warning: Permanently added [{}]:{} ({}) to the list of known hosts.",
+                    self.host,
+                    self.port,
+                    key.algorithm()
+                );
+                Ok(true)
+            }
+            Err(keys::Error::KeyChanged { .. }) => Err(Error::HostKeyChanged {
+                host: self.host.clone(),
+                port: self.port,
+                path: self.known_hosts.clone(),
+            }),
+            Err(err) => Err(Error::Keys(err)),
+        }
+    }
+}
+
+/// Connection to a host, authenticated with a secret key
+pub struct Session {
+    runner: SystemRunner,
+    handle: client::Handle<Handler>,
+}
+
+impl Session {
+    /// Connect to `host` at `port` as `user`, with the secret key in the file
+    /// `secret_key_path`, and check the host key with the file `known_hosts`.
+    pub fn connect(
+        host: &str,
+        port: u16,
+        user: &str,
+        secret_key_path: &Path,
+        known_hosts: &Path,
+    ) -> Result<Session, Box<dyn std::error::Error>> {
+        let secret_key = keys::load_secret_key(secret_key_path, None).map_err(|err| {
+            format!(
+                "cannot load secret key {}: {err}",
+                secret_key_path.display()
+            )
+        })?;
+        let handler = Handler {
+            host: host.to_string(),
+            port,
+            known_hosts: known_hosts.to_path_buf(),
+        };
+        let config = Arc::new(client::Config {
+            keepalive_interval: Some(std::time::Duration::from_secs(30)),
+            ..Default::default()
+        });
+        let runner = System::new();
+        let handle = runner.block_on(async {
+            debug!("connecting to {host}:{port}");
+            let mut handle = client::connect(config, (host, port), handler).await?;
+            let hash_alg = handle.best_supported_rsa_hash().await?.flatten();
+            let auth = handle
+                .authenticate_publickey(
+                    user,
+                    PrivateKeyWithHashAlg::new(Arc::new(secret_key), hash_alg),
+                )
+                .await?;
+            if !auth.success() {
+                return Err("secret key was not accepted by host".into());
+            }
+            Ok::<_, Box<dyn std::error::Error>>(handle)
+        })?;
+        Ok(Session { runner, handle })
+    }
+
+    /// Run interactive shell or command, and return the exit code.
+    ///
+    /// Input is read from stdin, and output is written to stdout and stderr.
+    /// In interactive mode, a pseudoterminal is requested, stdin is put in raw
+    /// mode if it is a terminal, and changes in the size of the terminal are
+    /// sent to the host. As with `ssh`, the exit code is 255 if the command
+    /// was terminated by a signal.
+    pub fn run(&self, mode: Mode) -> Result<u32, Box<dyn std::error::Error>> {
+        let interactive = matches!(mode, Mode::Interactive);
+        self.runner.block_on(async {
+            let mut channel = self.handle.channel_open_session().await?;
+            match mode {
+                Mode::Interactive => {
+                    let (cols, rows) = tty::size().unwrap_or((80, 24));
+                    let term = std::env::var("TERM").unwrap_or_else(|_| "xterm".into());
+                    channel
+                        .request_pty(false, &term, cols.into(), rows.into(), 0, 0, &[])
+                        .await?;
+                    channel.request_shell(true).await?;
+                }
+                Mode::Command(command) => channel.exec(true, command).await?,
+            }
+
+            let _raw_mode = if interactive && std::io::stdin().is_terminal() {
+                Some(tty::RawMode::new())
+            } else {
+                None
+            };
+            let mut input_rx = tty::forward_stdin();
+            let mut input_open = true;
+            let mut size = tty::size();
+            let mut resize_check = tokio::time::interval(std::time::Duration::from_millis(250));
+            let mut stdout = std::io::stdout();
+            let mut stderr = std::io::stderr();
+            let mut exit_code = None;
+
+            loop {
+                tokio::select! {
+                    message = channel.wait() => match message {
+                        Some(ChannelMsg::Data { data }) => {
+                            stdout.write_all(&data)?;
+                            stdout.flush()?;
+                        }
+                        Some(ChannelMsg::ExtendedData { data, ext: 1 }) => {
+                            stderr.write_all(&data)?;
+                        }
+                        Some(ChannelMsg::ExitStatus { exit_status }) => {
+                            exit_code = Some(exit_status);
+                        }
+                        Some(ChannelMsg::ExitSignal { .. }) => exit_code = Some(255),
+                        Some(ChannelMsg::Close) | None => break,
+                        Some(_) => {}
+                    },
+                    input = input_rx.recv(), if input_open => match input {
+                        Some(data) => channel.data(&data[..]).await?,
+                        None => {
+                            input_open = false;
+                            channel.eof().await?;
+                        }
+                    },
+                    _ = resize_check.tick(), if interactive => {
+                        let current = tty::size();
+                        if current != size {
+                            size = current;
+                            if let Some((cols, rows)) = size {
+                                channel.window_change(cols.into(), rows.into(), 0, 0).await?;
+                            }
+                        }
+                    }
+                }
+            }
+            exit_code.ok_or_else(|| "connection to host closed without exit status".into())
+        })
+    }
+
+    async fn sftp(&self) -> Result<SftpSession, Box<dyn std::error::Error>> {
+        let channel = self.handle.channel_open_session().await?;
+        channel.request_subsystem(true, "sftp").await?;
+        Ok(SftpSession::new(channel.into_stream()).await?)
+    }
+
+    /// Copy local file to `remote_path` on host via SFTP, and return the
+    /// number of bytes that were copied.
+    pub fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let mut local = std::fs::File::open(local_path)?;
+        self.runner.block_on(async {
+            let sftp = self.sftp().await?;
+            let mut remote = sftp.create(remote_path).await?;
+            let mut buf = vec![0; 32 * 1024];
+            let mut copied = 0;
+            loop {
+                let n = local.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                remote.write_all(&buf[..n]).await?;
+                copied += n as u64;
+            }
+            remote.shutdown().await?;
+            sftp.close().await?;
+            Ok(copied)
+        })
+    }
+
+    /// Copy `remote_path` on host to local file via SFTP, and return the number
+    /// of bytes that were copied.
+    pub fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        self.runner.block_on(async {
+            let sftp = self.sftp().await?;
+            let data = sftp.read(remote_path).await?;
+            sftp.close().await?;
+            std::fs::write(local_path, &data)?;
+            Ok(data.len() as u64)
+        })
+    }
+
+    /// Disconnect from host.
+    pub fn close(self) -> Result<(), Box<dyn std::error::Error>> {
+        self.runner.block_on(async {
+            self.handle
+                .disconnect(russh::Disconnect::ByApplication, "", "en")
+                .await
+        })?;
+        Ok(())
+    }
+}
+
+/// Connect to instance, which must be READY.
+///
+/// `instance` is details of the instance, as from [`crate::Client::instance_info()`].
+pub fn connect_instance(
+    instance: &serde_json::Value,
+    secret_key_path: &Path,
+) -> Result<Session, Box<dyn std::error::Error>> {
+    let (user, host, port) = crate::ops::ssh_address(instance)?;
+    let known_hosts = known_hosts_path().ok_or("cannot determine rerobots data directory")?;
+    Session::connect(&host, port, user, secret_key_path, &known_hosts)
+}
@@ -0,0 +1,487 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::claims::unix_now;
+use crate::client::{is_transient, Client};
+
+struct OpError {
+    msg: String,
+}
+impl std::error::Error for OpError {}
+
+impl std::fmt::Display for OpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl std::fmt::Debug for OpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl OpError {
+    fn newbox<T, S: ToString>(msg: S) -> Result<T, Box<dyn std::error::Error>> {
+        Err(Box::new(OpError {
+            msg: msg.to_string(),
+        }))
+    }
+}
+
+/// Get public key (PEM) with which API tokens are signed.
+///
+/// The key is fetched once from the API and then cached, per API origin, in
+/// the cache directory, e.g., `~/.cache/rerobots`. If `refresh`, then the
+/// cached key is ignored and replaced.
+pub fn public_key(client: &Client, refresh: bool) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let origin_name: String = client
+        .origin()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let cache_path =
+        crate::config::cache_dir().map(|d| d.join(format!("public-key-{origin_name}.pem")));
+    if !refresh {
+        if let Some(path) = &cache_path {
+            if let Ok(key) = std::fs::read(path) {
+                debug!("using public key cached at {}", path.display());
+                return Ok(key);
+            }
+        }
+    }
+    let key = client.public_key()?;
+    if let Some(path) = &cache_path {
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, &key));
+        if let Err(err) = result {
+            warn!("cannot cache public key at {}: {}", path.display(), err);
+        }
+    }
+    Ok(key.into_bytes())
+}
+
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// Get details of several workspace deployments concurrently.
+///
+/// Results are in the same order as `wdeployment_ids`.
+pub fn wdeployments_info<S: AsRef<str> + Sync>(
+    client: &Client,
+    wdeployment_ids: &[S],
+) -> Vec<Result<serde_json::Value, Box<dyn std::error::Error>>> {
+    concurrently(wdeployment_ids, |wdid| client.wdeployment_info(wdid))
+}
+
+/// Get details of several instances, as from [`Client::instance_info()`].
+///
+/// Requests are made concurrently, as for [`wdeployments_info()`].
+pub fn instances_info<S: AsRef<str> + Sync>(
+    client: &Client,
+    instance_ids: &[S],
+) -> Vec<Result<serde_json::Value, Box<dyn std::error::Error>>> {
+    concurrently(instance_ids, |inid| client.instance_info(Some(inid)))
+}
+
+fn concurrently<S, F>(
+    ids: &[S],
+    fetch: F,
+) -> Vec<Result<serde_json::Value, Box<dyn std::error::Error>>>
+where
+    S: AsRef<str> + Sync,
+    F: Fn(&str) -> Result<serde_json::Value, Box<dyn std::error::Error>> + Sync,
+{
+    let fetch = &fetch;
+    let mut results = Vec::with_capacity(ids.len());
+    for chunk in ids.chunks(MAX_CONCURRENT_REQUESTS) {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|id| scope.spawn(move || fetch(id.as_ref()).map_err(|err| err.to_string())))
+                .collect();
+            for handle in handles {
+                let result = match handle.join() {
+                    Ok(r) => r.map_err(|err| err.into()),
+                    Err(_) => OpError::newbox("request thread panicked"),
+                };
+                results.push(result);
+            }
+        });
+    }
+    results
+}
+
+/// Time until instance expires, or None if it has no expiration.
+///
+/// `instance` is details of the instance, as from [`Client::instance_info()`],
+/// where `expiration` is in seconds since the Unix epoch.
+pub fn remaining_time(instance: &serde_json::Value) -> Option<std::time::Duration> {
+    let expiration = instance["expiration"].as_u64()?;
+    Some(std::time::Duration::from_secs(
+        expiration.saturating_sub(unix_now()),
+    ))
+}
+
+/// When and by how much to extend an instance, as for [`with_auto_extend()`]
+pub struct AutoExtend {
+    /// Time to request with each extension
+    pub by: std::time::Duration,
+
+    /// Extend when the remaining time is at most this
+    pub margin: std::time::Duration,
+
+    /// How often to check the remaining time
+    pub poll_interval: std::time::Duration,
+}
+
+impl Default for AutoExtend {
+    fn default() -> Self {
+        AutoExtend {
+            by: std::time::Duration::from_secs(3600),
+            margin: std::time::Duration::from_secs(600),
+            poll_interval: std::time::Duration::from_secs(60),
+        }
+    }
+}
+
+/// Call `f` while extending the instance whenever it is about to expire.
+///
+/// After each attempt to extend, `on_result` is called with the new
+/// expiration or the reason that the extension was refused. After a refusal,
+/// no more attempts are made.
+pub fn with_auto_extend<T, F, R>(
+    client: &Client,
+    instance_id: &str,
+    policy: &AutoExtend,
+    mut on_result: R,
+    f: F,
+) -> T
+where
+    F: FnOnce() -> T,
+    R: FnMut(Result<u64, String>) + Send,
+{
+    let (stop, stopped) = std::sync::mpsc::channel::<()>();
+    std::thread::scope(|scope| {
+        scope.spawn(move || loop {
+            match client.instance_info(Some(instance_id)) {
+                Ok(payload) => {
+                    if remaining_time(&payload).is_some_and(|r| r <= policy.margin) {
+                        match client.extend(Some(instance_id), policy.by) {
+                            Ok(payload) => match payload["expiration"].as_u64() {
+                                Some(expiration) => on_result(Ok(expiration)),
+                                None => {
+                                    on_result(Err("response does not have expiration".into()));
+                                    return;
+                                }
+                            },
+                            Err(err) => {
+                                on_result(Err(err.to_string()));
+                                return;
+                            }
+                        }
+                    }
+                }
+                Err(err) => warn!("cannot get details of instance {instance_id}: {err}"),
+            }
+            if !matches!(
+                stopped.recv_timeout(policy.poll_interval),
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout)
+            ) {
+                return;
+            }
+        });
+        let result = f();
+        drop(stop);
+        result
+    })
+}
+
+/// Poll instance status until it is not INIT.
+///
+/// If `instance_id` is None, then this applies to the current (INIT or READY)
+/// instance. The last instance details are returned, which the caller should
+/// check to learn whether the status is READY. If `timeout` is given and the
+/// instance is still INIT after it, an error is returned.
+pub fn wait_until_ready<S: ToString>(
+    client: &Client,
+    instance_id: Option<S>,
+    timeout: Option<std::time::Duration>,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let start = std::time::Instant::now();
+    let mut instance_id = instance_id.map(|s| s.to_string());
+    loop {
+        let payload = client.instance_info(instance_id.clone())?;
+        let status = payload["status"]
+            .as_str()
+            .ok_or("Instance status should be string")?;
+        if status != "INIT" {
+            return Ok(payload);
+        }
+        if let Some(td) = timeout {
+            if start.elapsed() > td {
+                return OpError::newbox("timed out waiting for instance to be READY");
+            }
+        }
+        if instance_id.is_none() {
+            instance_id = Some(
+                payload["id"]
+                    .as_str()
+                    .ok_or("Instance id should be string")?
+                    .to_string(),
+            );
+        }
+        std::thread::sleep(std::time::Duration::new(1, 0));
+    }
+}
+
+/// Launch instance and block until it is READY.
+///
+/// Details of the new instance are returned, as from
+/// [`Client::instance_info()`]. If the instance reaches a status other than
+/// READY, e.g., INIT_FAIL, then an error is returned.
+pub fn launch_and_wait(
+    client: &Client,
+    wdid_or_wtype: &str,
+    public_key: Option<String>,
+    timeout: Option<std::time::Duration>,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let payload = client.launch(wdid_or_wtype, public_key)?;
+    let instance_id = payload["id"]
+        .as_str()
+        .ok_or("New instance ID should be string")?;
+    let payload = wait_until_ready(client, Some(instance_id), timeout)?;
+    let status = payload["status"]
+        .as_str()
+        .ok_or("Instance status should be string")?;
+    if status != "READY" {
+        return OpError::newbox(format!("instance {instance_id} is {status}"));
+    }
+    Ok(payload)
+}
+
+/// Poll reservation until it is fulfilled, and return the new instance ID.
+///
+/// `progress` is called after each poll with the reservation details, or with
+/// null if the poll failed with a [transient](is_transient) error, e.g., a
+/// network error, which is logged and retried. Other errors are returned.
+/// Waiting stops with an error if `progress` returns false. If `timeout` is
+/// given and the reservation is still pending after it, an error is returned;
+/// the reservation is not canceled.
+pub fn wait_for_reservation<F: FnMut(&serde_json::Value) -> bool>(
+    client: &Client,
+    reservation_id: &str,
+    timeout: Option<std::time::Duration>,
+    poll_interval: std::time::Duration,
+    mut progress: F,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let start = std::time::Instant::now();
+    loop {
+        match client.reservation_info(reservation_id) {
+            Ok(payload) => {
+                if !progress(&payload) {
+                    return OpError::newbox(format!(
+                        "stopped waiting for reservation {reservation_id}"
+                    ));
+                }
+                let status = payload["status"]
+                    .as_str()
+                    .ok_or("Reservation status should be string")?;
+                match status {
+                    "FULFILLED" => {
+                        return match payload["instance"].as_str() {
+                            Some(instance_id) => Ok(instance_id.to_string()),
+                            None => OpError::newbox("fulfilled reservation does not have instance"),
+                        };
+                    }
+                    "PENDING" => {}
+                    _ => {
+                        return OpError::newbox(format!("reservation {reservation_id} is {status}"))
+                    }
+                }
+            }
+            Err(err) if !is_transient(err.as_ref()) => return Err(err),
+            Err(err) => {
+                warn!("cannot get details of reservation {reservation_id}: {err}");
+                if !progress(&serde_json::Value::Null) {
+                    return OpError::newbox(format!(
+                        "stopped waiting for reservation {reservation_id}"
+                    ));
+                }
+            }
+        }
+        if let Some(t) = timeout {
+            if start.elapsed() > t {
+                return OpError::newbox(format!(
+                    "timed out waiting for reservation {reservation_id}"
+                ));
+            }
+        }
+        std::thread::sleep(poll_interval);
+    }
+}
+
+/// Enable add-on of instance, if needed, and block until it is active.
+///
+/// `progress` is called with the add-on status after each poll, or with null
+/// if the add-on is not enabled yet. The last status is returned.
+pub fn enable_addon_and_wait<F: FnMut(&serde_json::Value)>(
+    client: &Client,
+    addon: &str,
+    instance_id: &str,
+    timeout: Option<std::time::Duration>,
+    mut progress: F,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let start = std::time::Instant::now();
+    let mut enabled = false;
+    loop {
+        match client.addon_status(addon, instance_id)? {
+            Some(payload) => {
+                progress(&payload);
+                if payload["status"] == "active" {
+                    return Ok(payload);
+                }
+            }
+            None => {
+                progress(&serde_json::Value::Null);
+                if !enabled {
+                    client.enable_addon(addon, instance_id)?;
+                    enabled = true;
+                    continue;
+                }
+            }
+        }
+        if let Some(t) = timeout {
+            if start.elapsed() > t {
+                return OpError::newbox(format!(
+                    "timed out waiting for add-on {addon} to be active"
+                ));
+            }
+        }
+        std::thread::sleep(std::time::Duration::new(1, 0));
+    }
+}
+
+/// User, host, and port for SSH to instance, which must be READY
+pub(crate) fn ssh_address(
+    instance: &serde_json::Value,
+) -> Result<(&'static str, String, u16), Box<dyn std::error::Error>> {
+    let status = instance["status"]
+        .as_str()
+        .ok_or("Instance status should be string")?;
+    if status != "READY" {
+        return OpError::newbox("Error: instance is not READY");
+    }
+    let fwd = instance["fwd"]
+        .as_object()
+        .ok_or("In response, fwd should be JSON object")?;
+    let ipv4 = fwd["ipv4"].as_str().ok_or("ipv4 should be string")?;
+    let port = fwd["port"].as_u64().ok_or("port should be integer")?;
+    Ok(("root", ipv4.to_string(), u16::try_from(port)?))
+}
+
+/// Create ssh command to connect to instance.
+///
+/// `instance` is details of the instance, as from [`Client::instance_info()`].
+/// `ssh_args` are passed to ssh before the destination, and `secret_key_path`
+/// is used with `-i` unless `ssh_args` already includes `-i`.
+pub fn ssh_command_for(
+    instance: &serde_json::Value,
+    secret_key_path: Option<&str>,
+    ssh_args: &[&str],
+) -> Result<std::process::Command, Box<dyn std::error::Error>> {
+    let (username, ipv4, port) = ssh_address(instance)?;
+
+    let mut cmd = std::process::Command::new("ssh");
+    let mut gave_secretkey = false;
+    for arg in ssh_args.iter() {
+        if arg == &"-i" {
+            gave_secretkey = true;
+        }
+        cmd.arg(arg);
+    }
+    if let Some(secret_key_path) = secret_key_path {
+        if !gave_secretkey {
+            cmd.arg("-i").arg(secret_key_path);
+        }
+    }
+    cmd.arg("-p")
+        .arg(port.to_string())
+        .arg(format!("{username}@{ipv4}"));
+    Ok(cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{remaining_time, ssh_command_for};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    fn ready_instance() -> serde_json::Value {
+        serde_json::json!({
+            "id": "b8a6b3e5-4ab8-4ab1-a7b0-a2e0c1f0e0a8",
+            "status": "READY",
+            "fwd": {
+                "ipv4": "147.75.70.51",
+                "port": 2210
+            }
+        })
+    }
+
+    #[test]
+    fn ssh_command_with_key() -> TestResult {
+        let cmd = ssh_command_for(&ready_instance(), Some("key.pem"), &["-v"])?;
+        assert_eq!(cmd.get_program(), "ssh");
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(
+            args,
+            ["-v", "-i", "key.pem", "-p", "2210", "root@147.75.70.51"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ssh_command_given_key_in_args() -> TestResult {
+        let cmd = ssh_command_for(&ready_instance(), Some("key.pem"), &["-i", "other.pem"])?;
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(args, ["-i", "other.pem", "-p", "2210", "root@147.75.70.51"]);
+        Ok(())
+    }
+
+    #[test]
+    fn ssh_command_requires_ready() {
+        let mut instance = ready_instance();
+        instance["status"] = "INIT".into();
+        assert!(ssh_command_for(&instance, None, &[]).is_err());
+    }
+
+    #[test]
+    fn remaining() {
+        let mut instance = ready_instance();
+        assert_eq!(remaining_time(&instance), None);
+        instance["expiration"] = 1.into();
+        assert_eq!(remaining_time(&instance), Some(std::time::Duration::ZERO));
+        instance["expiration"] = (super::unix_now() + 7200).into();
+        assert!(
+            remaining_time(&instance).is_some_and(|r| r.as_secs() > 7100 && r.as_secs() <= 7200)
+        );
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Output formatting of API responses
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PrintingFormat {
+    Default,
+    Yaml,
+    Json,
+}
+
+/// Render API response in the given format.
+///
+/// The default format for raw payloads is JSON.
+pub fn render(payload: &serde_json::Value, pformat: PrintingFormat) -> String {
+    if pformat == PrintingFormat::Yaml {
+        serde_yaml::to_string(payload).expect("JSON value can be serialized to YAML")
+    } else {
+        // pformat == PrintingFormat::Json or PrintingFormat::Default
+        serde_json::to_string_pretty(payload).expect("JSON value can be serialized to JSON")
+    }
+}
+
+/// Format Unix time as RFC 3339 in UTC, e.g., `2026-10-18T09:30:00Z`
+pub fn format_timestamp(utime: u64) -> String {
+    match chrono::DateTime::from_timestamp(utime as i64, 0) {
+        Some(t) => t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        None => utime.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_timestamp, render, PrintingFormat};
+
+    #[test]
+    fn timestamp() {
+        assert_eq!(format_timestamp(1762592000), "2025-11-08T08:53:20Z");
+    }
+
+    #[test]
+    fn render_json() {
+        let payload = serde_json::json!({"id": "abc", "status": "READY"});
+        let expected = "{\n  \"id\": \"abc\",\n  \"status\": \"READY\"\n}";
+        assert_eq!(render(&payload, PrintingFormat::Json), expected);
+        assert_eq!(render(&payload, PrintingFormat::Default), expected);
+    }
+
+    #[test]
+    fn render_yaml() {
+        let payload = serde_json::json!({"id": "abc", "status": "READY"});
+        assert_eq!(
+            render(&payload, PrintingFormat::Yaml),
+            "---\nid: abc\nstatus: READY\n"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local relay of HTTP and WebSocket traffic to a remote origin, e.g., the
+//! proxy that is provided by the `mistyproxy` add-on
+//!
+//! Each connection carries one HTTP request, or one WebSocket after the
+//! upgrade. The request target is prefixed with the path of the remote URL,
+//! and the `Host` header is replaced; everything else is passed as is.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use actix::System;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::rustls;
+
+/// Largest request head that is accepted
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// Remote origin and path prefix
+#[derive(Clone, Debug, PartialEq)]
+pub struct Upstream {
+    tls: bool,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Upstream {
+    /// Parse URL with scheme `http`, `https`, `ws`, or `wss`.
+    pub fn parse(url: &str) -> Result<Upstream, Box<dyn std::error::Error>> {
+        let (tls, rest) = if let Some(rest) = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("wss://"))
+        {
+            (true, rest)
+        } else if let Some(rest) = url
+            .strip_prefix("http://")
+            .or_else(|| url.strip_prefix("ws://"))
+        {
+            (false, rest)
+        } else {
+            return Err(format!("unsupported URL: {url}").into());
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("invalid port in URL: {url}"))?,
+            ),
+            None => (authority, if tls { 443 } else { 80 }),
+        };
+        if host.is_empty() {
+            return Err(format!("no host in URL: {url}").into());
+        }
+        Ok(Upstream {
+            tls,
+            host: host.to_string(),
+            port,
+            path: path.trim_end_matches('/').to_string(),
+        })
+    }
+
+    fn host_header(&self) -> String {
+        if self.port == if self.tls { 443 } else { 80 } {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Rewrite request head for `upstream`. Return None if it is not valid.
+fn rewrite_head(head: &str, upstream: &Upstream, upgrade: bool) -> Option<String> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.splitn(3, ' ');
+    let method = request_line.next()?;
+    let target = request_line.next()?;
+    let version = request_line.next()?;
+    if !target.starts_with('/') {
+        return None;
+    }
+    let mut rewritten = format!(
+        "{method} {}{target} {version}\r\nHost: {}\r\n",
+        upstream.path,
+        upstream.host_header()
+    );
+    for line in lines.filter(|l| !l.is_empty()) {
+        let name = line.split(':').next().unwrap_or("").trim();
+        if name.eq_ignore_ascii_case("host")
+            || (!upgrade && name.eq_ignore_ascii_case("connection"))
+        {
+            continue;
+        }
+        rewritten.push_str(line);
+        rewritten.push_str("\r\n");
+    }
+    if !upgrade {
+        rewritten.push_str("Connection: close\r\n");
+    }
+    rewritten.push_str("\r\n");
+    Some(rewritten)
+}
+
+fn is_upgrade(head: &str) -> bool {
+    head.split("\r\n").skip(1).any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("upgrade")
+                && value.trim().eq_ignore_ascii_case("websocket")
+        })
+    })
+}
+
+fn tls_connector() -> tokio_rustls::TlsConnector {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+            ta.subject,
+            ta.spki,
+            ta.name_constraints,
+        )
+    }));
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    tokio_rustls::TlsConnector::from(Arc::new(config))
+}
+
+async fn splice<S: AsyncRead + AsyncWrite + Unpin>(
+    mut client: TcpStream,
+    mut remote: S,
+    head: &[u8],
+) -> std::io::Result<()> {
+    remote.write_all(head).await?;
+    tokio::io::copy_bidirectional(&mut client, &mut remote).await?;
+    Ok(())
+}
+
+async fn relay_connection(
+    mut client: TcpStream,
+    http: &Upstream,
+    websocket: &Upstream,
+    connector: &tokio_rustls::TlsConnector,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut buf = Vec::with_capacity(4096);
+    let head_end = loop {
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+        if buf.len() > MAX_HEAD_SIZE {
+            return Err("request head is too large".into());
+        }
+        let mut chunk = [0; 4096];
+        let n = client.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let head = std::str::from_utf8(&buf[..head_end])?;
+    let upgrade = is_upgrade(head);
+    let upstream = if upgrade { websocket } else { http };
+    let rewritten = match rewrite_head(head, upstream, upgrade) {
+        Some(h) => h,
+        None => {
+            client
+                .write_all(b"HTTP/1.1 400 Bad Request\r\nConnection: close\r\n\r\n")
+                .await?;
+            return Ok(());
+        }
+    };
+    debug!(
+        "relay {} to {}",
+        head.lines().next().unwrap_or(""),
+        upstream.host
+    );
+    let mut forward = rewritten.into_bytes();
+    forward.extend_from_slice(&buf[head_end..]);
+
+    let remote = TcpStream::connect((upstream.host.as_str(), upstream.port)).await?;
+    if upstream.tls {
+        let name = rustls::ServerName::try_from(upstream.host.as_str())?;
+        let remote = connector.connect(name, remote).await?;
+        splice(client, remote, &forward).await?;
+    } else {
+        splice(client, remote, &forward).await?;
+    }
+    Ok(())
+}
+
+/// Listen at `address`, and relay every connection until this process is
+/// stopped. `on_ready` is called with the local address after binding.
+///
+/// WebSocket connections are relayed to `websocket`, or else to `http`.
+pub fn run<F: FnOnce(SocketAddr)>(
+    address: &str,
+    http: Upstream,
+    websocket: Option<Upstream>,
+    on_ready: F,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let websocket = websocket.unwrap_or_else(|| http.clone());
+    let sys = System::new();
+    actix::SystemRunner::block_on(&sys, async move {
+        let listener = TcpListener::bind(address).await?;
+        on_ready(listener.local_addr()?);
+        let shared = Arc::new((http, websocket, tls_connector()));
+        loop {
+            let (client, peer) = listener.accept().await?;
+            let shared = shared.clone();
+            actix::spawn(async move {
+                let (http, websocket, connector) = &*shared;
+                if let Err(err) = relay_connection(client, http, websocket, connector).await {
+                    warn!("relay for {peer} failed: {err}");
+                }
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rewrite_head, Upstream};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn upstream() -> TestResult {
+        let upstream = Upstream::parse("https://proxy.rerobots.net/misty/abc/")?;
+        assert_eq!(upstream.host_header(), "proxy.rerobots.net");
+        assert_eq!(upstream.path, "/misty/abc");
+        let upstream = Upstream::parse("ws://127.0.0.1:8080")?;
+        assert_eq!(upstream.host_header(), "127.0.0.1:8080");
+        assert_eq!(upstream.path, "");
+        assert!(Upstream::parse("ftp://example.com").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite() -> TestResult {
+        let upstream = Upstream::parse("https://proxy.rerobots.net/misty/abc")?;
+        let head = "GET /api/device HTTP/1.1\r\nHost: localhost:8080\r\nConnection: keep-alive\r\nAccept: */*\r\n\r\n";
+        assert_eq!(
+            rewrite_head(head, &upstream, false).ok_or("should be valid")?,
+            "GET /misty/abc/api/device HTTP/1.1\r\nHost: proxy.rerobots.net\r\nAccept: */*\r\nConnection: close\r\n\r\n"
+        );
+        assert!(rewrite_head("GET http://x/ HTTP/1.1\r\n\r\n", &upstream, false).is_none());
+        Ok(())
+    }
+}
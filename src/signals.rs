@@ -0,0 +1,61 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Catching SIGINT and SIGTERM, so that work can be stopped cleanly
+
+use std::sync::atomic::{AtomicI32, Ordering};
+
+static RECEIVED: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn on_signal(signum: libc::c_int) {
+    RECEIVED.store(signum, Ordering::SeqCst);
+}
+
+/// Catch SIGINT and SIGTERM until dropped
+///
+/// Child processes do not inherit the handler, so they are interrupted
+/// as usual.
+pub struct Guard {
+    previous: Vec<(libc::c_int, libc::sighandler_t)>,
+}
+
+impl Guard {
+    pub fn new() -> Guard {
+        RECEIVED.store(0, Ordering::SeqCst);
+        let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        let previous = [libc::SIGINT, libc::SIGTERM]
+            .iter()
+            .map(|&signum| (signum, unsafe { libc::signal(signum, handler) }))
+            .collect();
+        Guard { previous }
+    }
+
+    /// Signal that was received, if any
+    pub fn received(&self) -> Option<libc::c_int> {
+        match RECEIVED.load(Ordering::SeqCst) {
+            0 => None,
+            signum => Some(signum),
+        }
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        for (signum, handler) in self.previous.iter() {
+            unsafe {
+                libc::signal(*signum, *handler);
+            }
+        }
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Options for launching instances, as given by flags or in a spec file
+//!
+//! A launch spec is a YAML file, e.g.,
+//!
+//! ```yaml
+//! workspace: fixed_misty2
+//! public_key: ~/.ssh/id_ed25519.pub
+//! max_duration: 2h
+//! repo:
+//!   url: https://github.com/rerobots/examples.git
+//!   branch: main
+//! startup_script: bootstrap.sh
+//! labels:
+//!   project: grasping
+//!   ci_run: "1234"
+//! ```
+//!
+//! Relative paths in a spec file are relative to the directory of the file.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::duration;
+
+/// Largest startup script that can be sent with a launch request
+pub const MAX_STARTUP_SCRIPT_SIZE: usize = 64 * 1024;
+
+/// Git repository to clone in the instance
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Repo {
+    pub url: String,
+
+    /// branch, tag, or commit; if not given, the default branch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+}
+
+/// Options for launching an instance
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LaunchSpec {
+    /// workspace deployment ID or workspace type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+
+    /// file containing public key for SSH access
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<PathBuf>,
+
+    /// maximum duration of the instance, e.g., `2h`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_duration: Option<String>,
+
+    /// git repository to clone in the instance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<Repo>,
+
+    /// file containing script to run in the instance when it is ready
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_script: Option<PathBuf>,
+
+    /// labels, e.g., `project: grasping`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+fn resolve(base: &Path, path: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~") {
+        if let Some(home) = crate::config::home_dir() {
+            return home.join(rest);
+        }
+    }
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    }
+}
+
+/// Check that label key and value are acceptable.
+///
+/// Keys have at most 63 characters from letters, digits, `-`, `_`, and `.`,
+/// and begin with a letter or digit. Values have at most 255 characters and
+/// no control characters.
+pub fn validate_label(key: &str, value: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let key_ok = !key.is_empty()
+        && key.len() <= 63
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && key.starts_with(|c: char| c.is_ascii_alphanumeric());
+    if !key_ok {
+        return Err(format!("invalid label key: {key}").into());
+    }
+    if value.len() > 255 || value.chars().any(|c| c.is_control()) {
+        return Err(format!("invalid value of label {key}").into());
+    }
+    Ok(())
+}
+
+/// Parse label given as `key=value`.
+pub fn parse_label(given: &str) -> Result<(String, String), Box<dyn std::error::Error>> {
+    match given.split_once('=') {
+        Some((key, value)) => {
+            validate_label(key, value)?;
+            Ok((key.to_string(), value.to_string()))
+        }
+        None => Err(format!("label should have the form key=value: {given}").into()),
+    }
+}
+
+fn validate_repo_url(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let ok = if let Some(rest) = ["https://", "http://", "ssh://", "git://"]
+        .iter()
+        .find_map(|scheme| url.strip_prefix(scheme))
+    {
+        rest.contains('/') && !rest.starts_with('/')
+    } else if let Some((host, path)) = url.split_once(':') {
+        // scp-like syntax, e.g., git@github.com:rerobots/cli.git
+        host.contains('@') && !path.is_empty()
+    } else {
+        false
+    };
+    if !ok || url.chars().any(|c| c.is_whitespace()) {
+        return Err(format!("invalid repository URL: {url}").into());
+    }
+    Ok(())
+}
+
+impl LaunchSpec {
+    /// Read spec from YAML file. Relative paths are resolved with respect to
+    /// the directory that contains the file, and a leading `~` is the home
+    /// directory.
+    pub fn from_file(path: &Path) -> Result<LaunchSpec, Box<dyn std::error::Error>> {
+        let text =
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut spec: LaunchSpec =
+            serde_yaml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        spec.public_key = spec.public_key.map(|p| resolve(base, &p));
+        spec.startup_script = spec.startup_script.map(|p| resolve(base, &p));
+        Ok(spec)
+    }
+
+    /// Combine with `other`, which takes precedence. Labels are merged.
+    pub fn merge(mut self, other: LaunchSpec) -> LaunchSpec {
+        if other.workspace.is_some() {
+            self.workspace = other.workspace;
+        }
+        if other.public_key.is_some() {
+            self.public_key = other.public_key;
+        }
+        if other.max_duration.is_some() {
+            self.max_duration = other.max_duration;
+        }
+        if other.repo.is_some() {
+            self.repo = other.repo;
+        }
+        if other.startup_script.is_some() {
+            self.startup_script = other.startup_script;
+        }
+        self.labels.extend(other.labels);
+        self
+    }
+
+    /// Check the spec and read the files that it refers to, and then create
+    /// the body of a launch request.
+    ///
+    /// All problems are reported before any request is made.
+    pub fn request_body(&self) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let mut body = serde_json::Map::new();
+        let mut problems = vec![];
+
+        if self.workspace.as_deref().unwrap_or("").is_empty() {
+            problems.push("workspace deployment ID or type is required".to_string());
+        }
+        if let Some(path) = &self.public_key {
+            match std::fs::read_to_string(path) {
+                Ok(s) => {
+                    body.insert("sshkey".into(), s.trim().into());
+                }
+                Err(err) => problems.push(format!("public key {}: {}", path.display(), err)),
+            }
+        }
+        if let Some(given) = &self.max_duration {
+            match duration::parse(given) {
+                Ok(d) if d.as_secs() > 0 => {
+                    body.insert("max_duration".into(), d.as_secs().into());
+                }
+                Ok(_) => problems.push("maximum duration must be positive".into()),
+                Err(err) => problems.push(format!("maximum duration: {err}")),
+            }
+        }
+        if let Some(repo) = &self.repo {
+            match validate_repo_url(&repo.url) {
+                Ok(()) => {
+                    body.insert("repo".into(), serde_json::to_value(repo)?);
+                }
+                Err(err) => problems.push(err.to_string()),
+            }
+            if let Some(branch) = &repo.branch {
+                if branch.is_empty() || branch.chars().any(|c| c.is_whitespace()) {
+                    problems.push(format!("invalid repository branch: {branch}"));
+                }
+            }
+        }
+        if let Some(path) = &self.startup_script {
+            match std::fs::read_to_string(path) {
+                Ok(s) if s.trim().is_empty() => {
+                    problems.push(format!("startup script {} is empty", path.display()))
+                }
+                Ok(s) if s.len() > MAX_STARTUP_SCRIPT_SIZE => problems.push(format!(
+                    "startup script {} is larger than {} bytes",
+                    path.display(),
+                    MAX_STARTUP_SCRIPT_SIZE
+                )),
+                Ok(s) => {
+                    body.insert("startup_script".into(), s.into());
+                }
+                Err(err) => problems.push(format!("startup script {}: {}", path.display(), err)),
+            }
+        }
+        for (key, value) in self.labels.iter() {
+            if let Err(err) = validate_label(key, value) {
+                problems.push(err.to_string());
+            }
+        }
+        if !self.labels.is_empty() {
+            body.insert("labels".into(), serde_json::to_value(&self.labels)?);
+        }
+
+        if !problems.is_empty() {
+            return Err(problems.join("; ").into());
+        }
+        Ok(serde_json::Value::Object(body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_label, LaunchSpec};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn spec_file() -> TestResult {
+        let dir = std::env::temp_dir().join(format!("rerobots-{}-spec", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("bootstrap.sh"), "#!/bin/sh\nmake\n")?;
+        let spec_path = dir.join("launch.yaml");
+        std::fs::write(
+            &spec_path,
+            "workspace: fixed_misty2\n\
+             max_duration: 2h\n\
+             repo:\n  url: https://github.com/rerobots/examples.git\n  branch: main\n\
+             startup_script: bootstrap.sh\n\
+             labels:\n  project: grasping\n",
+        )?;
+        let spec = LaunchSpec::from_file(&spec_path)?;
+        assert_eq!(spec.startup_script, Some(dir.join("bootstrap.sh")));
+        let body = spec.request_body()?;
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "max_duration": 7200,
+                "repo": {"url": "https://github.com/rerobots/examples.git", "branch": "main"},
+                "startup_script": "#!/bin/sh\nmake\n",
+                "labels": {"project": "grasping"},
+            })
+        );
+
+        std::fs::write(
+            &spec_path,
+            "workspace: fixed_misty2\npublic_key: ~/.ssh/id_ed25519.pub\n",
+        )?;
+        let spec = LaunchSpec::from_file(&spec_path)?;
+        let home = crate::config::home_dir().ok_or("home directory is not known")?;
+        assert_eq!(
+            spec.public_key,
+            Some(home.join(".ssh").join("id_ed25519.pub"))
+        );
+
+        std::fs::write(&spec_path, "workspace: fixed_misty2\nduration: 2h\n")?;
+        assert!(LaunchSpec::from_file(&spec_path).is_err());
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn validation() -> TestResult {
+        let spec = LaunchSpec {
+            max_duration: Some("0s".into()),
+            startup_script: Some("/nonexistent/bootstrap.sh".into()),
+            repo: Some(super::Repo {
+                url: "github.com/rerobots/examples".into(),
+                branch: None,
+            }),
+            ..LaunchSpec::default()
+        };
+        let err = spec.request_body().err().ok_or("should be invalid")?;
+        let err = err.to_string();
+        assert!(err.contains("workspace deployment ID or type is required"));
+        assert!(err.contains("maximum duration must be positive"));
+        assert!(err.contains("invalid repository URL"));
+        assert!(err.contains("startup script /nonexistent/bootstrap.sh"));
+        Ok(())
+    }
+
+    #[test]
+    fn labels() -> TestResult {
+        assert_eq!(
+            parse_label("ci_run=1234")?,
+            ("ci_run".to_string(), "1234".to_string())
+        );
+        assert_eq!(parse_label("note=a=b")?.1, "a=b");
+        assert!(parse_label("project").is_err());
+        assert!(parse_label("=x").is_err());
+        assert!(parse_label("-x=1").is_err());
+        assert!(parse_label("has space=1").is_err());
+        Ok(())
+    }
+}
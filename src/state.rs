@@ -0,0 +1,177 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local state about instances, such as labels
+//!
+//! The state is kept in `instances.json` in the data directory, e.g.,
+//! `~/.local/share/rerobots/instances.json`, and is keyed by instance ID.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::data_dir;
+
+pub type Labels = BTreeMap<String, String>;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InstanceState {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: Labels,
+}
+
+#[derive(Debug, Default)]
+pub struct State {
+    path: Option<PathBuf>,
+    instances: BTreeMap<String, InstanceState>,
+}
+
+/// Default path of the state file
+pub fn default_path() -> Option<PathBuf> {
+    data_dir().map(|d| d.join("instances.json"))
+}
+
+/// Parse selector of the form `key=value,key=value`, as given with `-l`.
+pub fn parse_selector<S: AsRef<str>>(given: &[S]) -> Result<Labels, Box<dyn std::error::Error>> {
+    let mut selector = Labels::new();
+    for part in given.iter().flat_map(|g| g.as_ref().split(',')) {
+        let (key, value) = crate::spec::parse_label(part)?;
+        selector.insert(key, value);
+    }
+    Ok(selector)
+}
+
+/// Format labels as `key=value,key=value`.
+pub fn format_labels(labels: &Labels) -> String {
+    labels
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+impl State {
+    /// Load state from the default path. If there is no state file yet,
+    /// then the state is empty.
+    pub fn load() -> Result<State, Box<dyn std::error::Error>> {
+        match default_path() {
+            Some(path) => State::load_from(&path),
+            None => Ok(State::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<State, Box<dyn std::error::Error>> {
+        let instances = match std::fs::read_to_string(path) {
+            Ok(text) => {
+                serde_json::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(format!("{}: {}", path.display(), err).into()),
+        };
+        Ok(State {
+            path: Some(path.to_path_buf()),
+            instances,
+        })
+    }
+
+    /// Write state. The file is replaced atomically, so concurrent readers
+    /// never see partial state.
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.path.as_ref().ok_or("no path for local state")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension(format!("json.{}", std::process::id()));
+        std::fs::write(&tmp, serde_json::to_string_pretty(&self.instances)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn labels(&self, instance_id: &str) -> Labels {
+        match self.instances.get(instance_id) {
+            Some(s) => s.labels.clone(),
+            None => Labels::new(),
+        }
+    }
+
+    /// Add or replace labels of instance.
+    pub fn add_labels(&mut self, instance_id: &str, labels: &Labels) {
+        let entry = self.instances.entry(instance_id.to_string()).or_default();
+        entry
+            .labels
+            .extend(labels.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+    /// Remove label of instance. Return whether it was present.
+    pub fn remove_label(&mut self, instance_id: &str, key: &str) -> bool {
+        match self.instances.get_mut(instance_id) {
+            Some(s) => s.labels.remove(key).is_some(),
+            None => false,
+        }
+    }
+
+    /// Forget about instance, e.g., after it is terminated.
+    pub fn remove(&mut self, instance_id: &str) {
+        self.instances.remove(instance_id);
+    }
+
+    /// Whether instance has all labels of `selector`
+    pub fn matches(&self, instance_id: &str, selector: &Labels) -> bool {
+        match self.instances.get(instance_id) {
+            Some(s) => selector.iter().all(|(k, v)| s.labels.get(k) == Some(v)),
+            None => selector.is_empty(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_labels, parse_selector, State};
+
+    type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+    #[test]
+    fn labels() -> TestResult {
+        let path =
+            std::env::temp_dir().join(format!("rerobots-{}-instances.json", std::process::id()));
+        let mut state = State::load_from(&path)?;
+        assert!(state.labels("a").is_empty());
+        state.add_labels("a", &parse_selector(&["project=grasping,ci_run=1"])?);
+        state.add_labels("b", &parse_selector(&["project=grasping", "ci_run=2"])?);
+        state.save()?;
+
+        let mut state = State::load_from(&path)?;
+        assert_eq!(
+            format_labels(&state.labels("a")),
+            "ci_run=1,project=grasping"
+        );
+        let selector = parse_selector(&["project=grasping"])?;
+        assert!(state.matches("a", &selector) && state.matches("b", &selector));
+        let selector = parse_selector(&["ci_run=2"])?;
+        assert!(!state.matches("a", &selector) && state.matches("b", &selector));
+        assert!(!state.matches("c", &selector));
+
+        assert!(state.remove_label("b", "ci_run"));
+        assert!(!state.remove_label("b", "ci_run"));
+        state.remove("a");
+        state.save()?;
+        let state = State::load_from(&path)?;
+        assert!(state.labels("a").is_empty());
+        assert_eq!(format_labels(&state.labels("b")), "project=grasping");
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
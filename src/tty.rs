@@ -0,0 +1,160 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interaction with the terminal
+
+use std::io::prelude::*;
+
+/// Print prompt to stderr and read a line from stdin without echo.
+///
+/// If stdin is not a terminal, then the line is read as is.
+pub fn read_hidden_line(prompt: &str) -> std::io::Result<String> {
+    eprint!("{prompt}");
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    {
+        let _guard = EchoOff::new();
+        std::io::stdin().read_line(&mut line)?;
+    }
+    eprintln!();
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Disable echo of stdin until dropped
+struct EchoOff {
+    #[cfg(unix)]
+    original: Option<libc::termios>,
+}
+
+#[cfg(unix)]
+impl EchoOff {
+    fn new() -> EchoOff {
+        let fd = libc::STDIN_FILENO;
+        // SAFETY: termios is plain data, and tcgetattr initializes it on success.
+        let mut term: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut term) } != 0 {
+            return EchoOff { original: None };
+        }
+        let original = term;
+        term.c_lflag &= !libc::ECHO;
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &term) } != 0 {
+            return EchoOff { original: None };
+        }
+        EchoOff {
+            original: Some(original),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for EchoOff {
+    fn drop(&mut self) {
+        if let Some(original) = &self.original {
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+impl EchoOff {
+    fn new() -> EchoOff {
+        EchoOff {}
+    }
+}
+
+/// Put stdin in raw mode until dropped, so that every key, including Ctrl-C,
+/// is read as is
+pub struct RawMode {
+    #[cfg(unix)]
+    original: Option<libc::termios>,
+}
+
+#[cfg(unix)]
+impl RawMode {
+    pub fn new() -> RawMode {
+        let fd = libc::STDIN_FILENO;
+        // SAFETY: termios is plain data, and tcgetattr initializes it on success.
+        let mut term: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut term) } != 0 {
+            return RawMode { original: None };
+        }
+        let original = term;
+        unsafe { libc::cfmakeraw(&mut term) };
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &term) } != 0 {
+            return RawMode { original: None };
+        }
+        RawMode {
+            original: Some(original),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if let Some(original) = &self.original {
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+impl RawMode {
+    pub fn new() -> RawMode {
+        RawMode {}
+    }
+}
+
+/// Size of the terminal at stdout as (columns, rows), or None if stdout is
+/// not a terminal
+#[cfg(unix)]
+pub fn size() -> Option<(u16, u16)> {
+    // SAFETY: winsize is plain data, and ioctl initializes it on success.
+    let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ws) } != 0 || ws.ws_col == 0
+    {
+        return None;
+    }
+    Some((ws.ws_col, ws.ws_row))
+}
+
+#[cfg(not(unix))]
+pub fn size() -> Option<(u16, u16)> {
+    None
+}
+
+/// Read stdin in a separate thread, and send what is read to the returned
+/// channel, which is closed at EOF
+pub fn forward_stdin() -> tokio::sync::mpsc::UnboundedReceiver<Vec<u8>> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut buf = [0; 4096];
+        loop {
+            match stdin.read(&mut buf) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => {
+                    if tx.send(buf[..n].to_vec()).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+    rx
+}
@@ -0,0 +1,137 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local VPN client for the `vpn` add-on
+//!
+//! The client is run as a child process: `openvpn` for OpenVPN
+//! configurations, or `wg-quick` for WireGuard configurations. It is torn
+//! down when this process is interrupted or terminated.
+
+use std::path::Path;
+
+/// Kind of VPN client configuration
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    OpenVpn,
+    WireGuard,
+}
+
+impl Kind {
+    /// Kind from `type` in the response to [`crate::Client::create_vpn_client()`]
+    pub fn from_type(given: &str) -> Result<Kind, Box<dyn std::error::Error>> {
+        match given {
+            "openvpn" => Ok(Kind::OpenVpn),
+            "wireguard" => Ok(Kind::WireGuard),
+            _ => Err(format!("unknown kind of VPN client: {given}").into()),
+        }
+    }
+
+    /// Kind from file name extension, where `.conf` is WireGuard and all
+    /// others are OpenVPN
+    pub fn from_path(path: &Path) -> Kind {
+        match path.extension() {
+            Some(ext) if ext == "conf" => Kind::WireGuard,
+            _ => Kind::OpenVpn,
+        }
+    }
+
+    /// Usual file name extension, which is recognized by [`Kind::from_path()`]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Kind::OpenVpn => "ovpn",
+            Kind::WireGuard => "conf",
+        }
+    }
+}
+
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Run VPN client until it exits or this process is interrupted or
+/// terminated, and return its exit code.
+#[cfg(unix)]
+pub fn up(config: &Path, kind: Kind) -> Result<i32, Box<dyn std::error::Error>> {
+    let guard = crate::signals::Guard::new();
+    match kind {
+        Kind::OpenVpn => {
+            let mut child = std::process::Command::new("openvpn")
+                .arg("--config")
+                .arg(config)
+                .spawn()
+                .map_err(|err| format!("cannot run openvpn: {err}"))?;
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    return Ok(status.code().unwrap_or(1));
+                }
+                if guard.received().is_some() {
+                    debug!("stopping openvpn");
+                    unsafe {
+                        libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+                    }
+                    child.wait()?;
+                    return Ok(0);
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+        Kind::WireGuard => {
+            let status = std::process::Command::new("wg-quick")
+                .arg("up")
+                .arg(config)
+                .status()
+                .map_err(|err| format!("cannot run wg-quick: {err}"))?;
+            if !status.success() {
+                return Ok(status.code().unwrap_or(1));
+            }
+            while guard.received().is_none() {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            debug!("stopping WireGuard interface");
+            let status = std::process::Command::new("wg-quick")
+                .arg("down")
+                .arg(config)
+                .status()?;
+            Ok(status.code().unwrap_or(1))
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn up(config: &Path, kind: Kind) -> Result<i32, Box<dyn std::error::Error>> {
+    match kind {
+        Kind::OpenVpn => {
+            let status = std::process::Command::new("openvpn")
+                .arg("--config")
+                .arg(config)
+                .status()
+                .map_err(|err| format!("cannot run openvpn: {err}"))?;
+            Ok(status.code().unwrap_or(1))
+        }
+        Kind::WireGuard => Err("WireGuard clients are only supported on Unix".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Kind;
+
+    #[test]
+    fn kinds() {
+        assert_eq!(Kind::from_path(Path::new("lab.ovpn")), Kind::OpenVpn);
+        assert_eq!(Kind::from_path(Path::new("wg0.conf")), Kind::WireGuard);
+        assert!(Kind::from_type("openvpn").is_ok_and(|k| k.extension() == "ovpn"));
+        assert!(Kind::from_type("ipsec").is_err());
+    }
+}
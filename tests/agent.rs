@@ -0,0 +1,289 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests with a stand-in for ssh-agent
+
+#![cfg(unix)]
+
+mod support;
+
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+
+use support::{ssh_fixture_path, temp_path, StandIn, TestResult};
+
+const INSTANCE_ID: &str = "c9b9c1d6-69c9-4b3e-9e2e-d5d2ea8bd3f1";
+
+/// Key held by the stand-in agent
+#[derive(Clone, Debug, PartialEq)]
+struct Identity {
+    public_blob: Vec<u8>,
+    comment: String,
+    lifetime: Option<u32>,
+}
+
+type Identities = Arc<Mutex<Vec<Identity>>>;
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> u8 {
+        let x = self.0[0];
+        self.0 = &self.0[1..];
+        x
+    }
+
+    fn u32(&mut self) -> u32 {
+        let x = u32::from_be_bytes([self.0[0], self.0[1], self.0[2], self.0[3]]);
+        self.0 = &self.0[4..];
+        x
+    }
+
+    fn string(&mut self) -> &'a [u8] {
+        let n = self.u32() as usize;
+        let s = &self.0[..n];
+        self.0 = &self.0[n..];
+        s
+    }
+}
+
+fn put_string(out: &mut Vec<u8>, s: &[u8]) {
+    out.extend_from_slice(&(s.len() as u32).to_be_bytes());
+    out.extend_from_slice(s);
+}
+
+/// Answer one request of the ssh-agent protocol.
+fn respond(request: &[u8], identities: &Identities) -> Vec<u8> {
+    let mut request = Reader(request);
+    let mut identities = identities.lock().expect("lock should not be poisoned");
+    match request.u8() {
+        // SSH2_AGENTC_REQUEST_IDENTITIES
+        11 => {
+            let mut response = vec![12];
+            response.extend_from_slice(&(identities.len() as u32).to_be_bytes());
+            for identity in identities.iter() {
+                put_string(&mut response, &identity.public_blob);
+                put_string(&mut response, identity.comment.as_bytes());
+            }
+            response
+        }
+        // SSH2_AGENTC_ADD_IDENTITY, SSH2_AGENTC_ADD_ID_CONSTRAINED
+        17 | 25 => {
+            let algorithm = request.string();
+            let mut public_blob = vec![];
+            put_string(&mut public_blob, algorithm);
+            match algorithm {
+                b"ssh-rsa" => {
+                    let n = request.string();
+                    let e = request.string();
+                    put_string(&mut public_blob, e);
+                    put_string(&mut public_blob, n);
+                    for _ in 0..4 {
+                        request.string();
+                    }
+                }
+                _ => {
+                    put_string(&mut public_blob, request.string());
+                    request.string();
+                }
+            }
+            let comment = String::from_utf8_lossy(request.string()).to_string();
+            let mut lifetime = None;
+            while !request.0.is_empty() {
+                assert_eq!(request.u8(), 1, "only lifetime constraints are expected");
+                lifetime = Some(request.u32());
+            }
+            identities.push(Identity {
+                public_blob,
+                comment,
+                lifetime,
+            });
+            vec![6]
+        }
+        // SSH2_AGENTC_REMOVE_IDENTITY
+        18 => {
+            let public_blob = request.string();
+            let before = identities.len();
+            identities.retain(|i| i.public_blob != public_blob);
+            if identities.len() < before {
+                vec![6]
+            } else {
+                vec![5]
+            }
+        }
+        _ => vec![5],
+    }
+}
+
+fn serve(mut stream: UnixStream, identities: Identities) {
+    loop {
+        let mut len = [0; 4];
+        if stream.read_exact(&mut len).is_err() {
+            return;
+        }
+        let mut request = vec![0; u32::from_be_bytes(len) as usize];
+        if stream.read_exact(&mut request).is_err() {
+            return;
+        }
+        let response = respond(&request, &identities);
+        if stream
+            .write_all(&(response.len() as u32).to_be_bytes())
+            .and_then(|()| stream.write_all(&response))
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// Start stand-in agent. Return path of its socket and the keys it holds.
+fn start_agent(name: &str) -> Result<(std::path::PathBuf, Identities), Box<dyn std::error::Error>> {
+    let path = temp_path(name);
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    let identities = Identities::default();
+    let held = identities.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let held = held.clone();
+            std::thread::spawn(move || serve(stream, held));
+        }
+    });
+    Ok((path, identities))
+}
+
+fn instance_and_key(standin: &mut StandIn) -> TestResult {
+    standin.add(serde_json::from_value(serde_json::json!({
+        "request": { "method": "GET", "path": format!("/instance/{INSTANCE_ID}") },
+        "response": {
+            "status": 200,
+            "body": {
+                "id": INSTANCE_ID,
+                "status": "READY",
+                "fwd": { "ipv4": "147.75.70.51", "port": 2210 },
+                "expiration": support::unix_now() + 3600
+            }
+        }
+    }))?)?;
+    let key = std::fs::read_to_string(ssh_fixture_path("client_key.pem"))?;
+    standin.add_sshkey(INSTANCE_ID, &key)
+}
+
+fn held(identities: &Identities) -> Vec<Identity> {
+    identities
+        .lock()
+        .expect("lock should not be poisoned")
+        .clone()
+}
+
+#[test]
+fn get_ssh_key_agent_and_terminate() -> TestResult {
+    let (socket, identities) = start_agent("agent.sock")?;
+    let data_dir = temp_path("agent-data");
+
+    let mut standin = StandIn::new();
+    instance_and_key(&mut standin)?;
+    let assert = standin
+        .command()?
+        .env("SSH_AUTH_SOCK", &socket)
+        .env("XDG_DATA_HOME", &data_dir)
+        .args(["get-ssh-key", "--agent", INSTANCE_ID])
+        .assert()
+        .success()
+        .stdout("");
+    standin.assert_all_served();
+    let stderr = String::from_utf8(assert.get_output().stderr.clone())?;
+    assert!(stderr.starts_with(&format!(
+        "Added secret key of instance {INSTANCE_ID} to ssh-agent for "
+    )));
+    let keys = held(&identities);
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].comment, format!("rerobots:{INSTANCE_ID}"));
+    let lifetime = keys[0].lifetime.ok_or("key should have lifetime")?;
+    assert!((3590..=3600).contains(&lifetime));
+    assert!(!data_dir.exists());
+
+    let standin = StandIn::replay("terminate")?;
+    standin
+        .command()?
+        .env("SSH_AUTH_SOCK", &socket)
+        .env("XDG_DATA_HOME", &data_dir)
+        .args(["terminate", INSTANCE_ID])
+        .assert()
+        .success()
+        .stderr(format!(
+            "Removed secret key of instance {INSTANCE_ID} from ssh-agent\n"
+        ));
+    assert!(held(&identities).is_empty());
+
+    if data_dir.exists() {
+        std::fs::remove_dir_all(&data_dir)?;
+    }
+    std::fs::remove_file(&socket)?;
+    Ok(())
+}
+
+#[test]
+fn ssh_adds_key_to_agent() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin_dir = temp_path("agent-bin");
+    std::fs::create_dir_all(&bin_dir)?;
+    let ssh = bin_dir.join("ssh");
+    std::fs::write(&ssh, "#!/bin/sh\necho \"$@\"\n")?;
+    std::fs::set_permissions(&ssh, std::fs::Permissions::from_mode(0o755))?;
+    let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH")?);
+    let (socket, identities) = start_agent("ssh-agent.sock")?;
+
+    let mut standin = StandIn::new();
+    instance_and_key(&mut standin)?;
+    standin
+        .command()?
+        .env("PATH", &path)
+        .env("SSH_AUTH_SOCK", &socket)
+        .args(["ssh", INSTANCE_ID])
+        .assert()
+        .success()
+        .stdout("-p 2210 root@147.75.70.51\n");
+    standin.assert_all_served();
+    assert_eq!(held(&identities).len(), 1);
+
+    // The key is not fetched again while the agent has it
+    let mut standin = StandIn::new();
+    standin.add(serde_json::from_value(serde_json::json!({
+        "request": { "method": "GET", "path": format!("/instance/{INSTANCE_ID}") },
+        "response": {
+            "status": 200,
+            "body": {
+                "id": INSTANCE_ID,
+                "status": "READY",
+                "fwd": { "ipv4": "147.75.70.51", "port": 2210 }
+            }
+        }
+    }))?)?;
+    standin
+        .command()?
+        .env("PATH", &path)
+        .env("SSH_AUTH_SOCK", &socket)
+        .args(["ssh", INSTANCE_ID])
+        .assert()
+        .success();
+    standin.assert_all_served();
+    assert_eq!(held(&identities).len(), 1);
+
+    std::fs::remove_dir_all(&bin_dir)?;
+    std::fs::remove_file(&socket)?;
+    Ok(())
+}
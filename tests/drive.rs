@@ -0,0 +1,186 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod support;
+
+use std::io::Read;
+use std::sync::mpsc;
+
+use support::{temp_path, StandIn, TestResult};
+
+const INSTANCE_ID: &str = "c9b9c1d6-69c9-4b3e-9e2e-d5d2ea8bd3f1";
+
+/// Paths of files in archive (tar+gzip)
+fn archive_files(archive: &[u8]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut files = vec![];
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_file() {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            files.push(format!("{}: {content}", entry.path()?.display()));
+        }
+    }
+    Ok(files)
+}
+
+fn checksum(data: &[u8]) -> String {
+    openssl::sha::sha256(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[test]
+fn push() -> TestResult {
+    let local_dir = temp_path("push-project");
+    std::fs::create_dir_all(local_dir.join("src"))?;
+    std::fs::write(local_dir.join(".gitignore"), "build/\n")?;
+    std::fs::write(local_dir.join("src").join("main.py"), "print(1)\n")?;
+    std::fs::create_dir_all(local_dir.join("build"))?;
+    std::fs::write(local_dir.join("build").join("out.bin"), "binary")?;
+
+    let mut standin = StandIn::with_ready_addon(
+        INSTANCE_ID,
+        "drive",
+        serde_json::json!({"status": "active"}),
+    )?;
+    let (tx, rx) = mpsc::channel();
+    standin.add_responder(
+        "POST",
+        &format!("/addon/drive/{INSTANCE_ID}/push"),
+        move |body| {
+            let archive = body["archive"]
+                .as_str()
+                .and_then(|a| openssl::base64::decode_block(a).ok())
+                .unwrap_or_default();
+            let _ = tx.send((body.clone(), archive.clone()));
+            serde_json::json!({ "path": body["path"], "sha256": checksum(&archive) })
+        },
+    );
+    let assert = standin
+        .command()?
+        .args(["push", INSTANCE_ID])
+        .arg(&local_dir)
+        .arg("work/project")
+        .assert()
+        .success();
+    standin.assert_all_served();
+
+    let (body, archive) = rx.recv()?;
+    assert_eq!(body["path"], "work/project");
+    assert_eq!(body["sha256"], checksum(&archive));
+    assert_eq!(
+        archive_files(&archive)?,
+        vec![".gitignore: build/\n", "src/main.py: print(1)\n"]
+    );
+    let output = assert.get_output();
+    assert_eq!(
+        String::from_utf8(output.stdout.clone())?,
+        format!("{}  work/project\n", checksum(&archive))
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr.clone())?,
+        format!(
+            "Packed 2 files (16 bytes) into archive of {} bytes\n\
+             Sending archive to work/project on instance {INSTANCE_ID}\n",
+            archive.len()
+        )
+    );
+
+    std::fs::remove_dir_all(&local_dir)?;
+    Ok(())
+}
+
+fn pull_response(
+    archive: &[u8],
+    sha256: &str,
+) -> Result<rerobots_cli::fixture::Exchange, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_value(serde_json::json!({
+        "request": {
+            "method": "POST",
+            "path": format!("/addon/drive/{INSTANCE_ID}/pull"),
+            "body": { "path": "logs" }
+        },
+        "response": {
+            "status": 200,
+            "body": {
+                "archive": openssl::base64::encode_block(archive),
+                "sha256": sha256
+            }
+        }
+    }))?)
+}
+
+#[test]
+fn pull() -> TestResult {
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::default(),
+    ));
+    let mut header = tar::Header::new_gnu();
+    header.set_size(6);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, "logs/run.txt", "ok 42\n".as_bytes())?;
+    let archive = builder.into_inner()?.finish()?;
+
+    let local_dir = temp_path("pull-out");
+    let mut standin = StandIn::with_ready_addon(
+        INSTANCE_ID,
+        "drive",
+        serde_json::json!({"status": "active"}),
+    )?;
+    standin.add(pull_response(&archive, &checksum(&archive))?)?;
+    standin
+        .command()?
+        .args(["pull", INSTANCE_ID, "logs"])
+        .arg(&local_dir)
+        .assert()
+        .success()
+        .stdout(format!("{}  {}\n", checksum(&archive), local_dir.display()))
+        .stderr(format!(
+            "Receiving archive of logs from instance {INSTANCE_ID}\n\
+             Unpacked 1 file (6 bytes) into {}\n",
+            local_dir.display()
+        ));
+    standin.assert_all_served();
+    assert_eq!(
+        std::fs::read_to_string(local_dir.join("logs").join("run.txt"))?,
+        "ok 42\n"
+    );
+    std::fs::remove_dir_all(&local_dir)?;
+
+    let bad_checksum = "0".repeat(64);
+    let mut standin = StandIn::with_ready_addon(
+        INSTANCE_ID,
+        "drive",
+        serde_json::json!({"status": "active"}),
+    )?;
+    standin.add(pull_response(&archive, &bad_checksum)?)?;
+    standin
+        .command()?
+        .args(["pull", INSTANCE_ID, "logs"])
+        .arg(&local_dir)
+        .assert()
+        .code(1)
+        .stderr(format!(
+            "Receiving archive of logs from instance {INSTANCE_ID}\n\
+             checksum mismatch: expected {bad_checksum}, but received {}\n",
+            checksum(&archive)
+        ));
+    assert!(!local_dir.exists());
+    Ok(())
+}
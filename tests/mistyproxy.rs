@@ -0,0 +1,157 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod support;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+
+use tungstenite::Message;
+
+use support::{request_header, RecordHandshake, StandIn, TestResult};
+
+const INSTANCE_ID: &str = "c9b9c1d6-69c9-4b3e-9e2e-d5d2ea8bd3f1";
+
+/// Stand-in for the proxied robot API, which serves two connections
+///
+/// The first is an HTTP request, and its head is sent to the returned channel
+/// and echoed as the response body. The second is a WebSocket, and its
+/// messages are echoed.
+fn robot_standin() -> Result<(String, mpsc::Receiver<String>), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let origin = listener.local_addr()?.to_string();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(
+        move || -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            let (stream, _) = listener.accept()?;
+            let mut reader = BufReader::new(stream.try_clone()?);
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line)?;
+                head.push_str(&line);
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+            }
+            drop(reader);
+            tx.send(head.clone())?;
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{head}",
+                head.len()
+            )?;
+            drop(stream);
+
+            let (stream, _) = listener.accept()?;
+            let mut socket = tungstenite::accept_hdr(
+                stream,
+                RecordHandshake::new(tx, |request| {
+                    format!("{} {}", request.uri(), request_header(request, "host"))
+                }),
+            )?;
+            loop {
+                match socket.read()? {
+                    Message::Text(text) => socket.send(Message::Text(text))?,
+                    Message::Close(_) => return Ok(()),
+                    _ => {}
+                }
+            }
+        },
+    );
+    Ok((origin, rx))
+}
+
+#[test]
+fn print_urls() -> TestResult {
+    let standin = StandIn::replay("addon_enable")?;
+    standin
+        .command()?
+        .args(["mistyproxy", INSTANCE_ID])
+        .assert()
+        .success()
+        .stdout(format!(
+            "https://proxy.rerobots.net/misty/{INSTANCE_ID}\n\
+             wss://proxy.rerobots.net/misty/{INSTANCE_ID}\n"
+        ))
+        .stderr(format!(
+            "Waiting for mistyproxy add-on of instance {INSTANCE_ID} to be active\n"
+        ));
+    standin.assert_all_served();
+    Ok(())
+}
+
+#[test]
+fn relay() -> TestResult {
+    let (origin, received) = robot_standin()?;
+    let mut standin = StandIn::new();
+    standin.add_addon(
+        INSTANCE_ID,
+        "mistyproxy",
+        serde_json::json!({
+            "status": "active",
+            "url": [
+                format!("http://{origin}/misty/{INSTANCE_ID}"),
+                format!("ws://{origin}/misty/{INSTANCE_ID}")
+            ]
+        }),
+    )?;
+    let mut child = standin
+        .std_command()
+        .args(["mistyproxy", INSTANCE_ID, "--listen", "127.0.0.1:0"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    let mut stderr = BufReader::new(child.stderr.take().ok_or("no stderr")?);
+    let mut line = String::new();
+    stderr.read_line(&mut line)?;
+    let local = line
+        .strip_prefix("Relaying http://")
+        .and_then(|rest| rest.split_once(' '))
+        .ok_or(format!("unexpected output: {line}"))?
+        .0
+        .to_string();
+
+    let mut stream = TcpStream::connect(&local)?;
+    write!(
+        stream,
+        "GET /api/device HTTP/1.1\r\nHost: {local}\r\nConnection: keep-alive\r\n\r\n"
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let expected_head = format!(
+        "GET /misty/{INSTANCE_ID}/api/device HTTP/1.1\r\n\
+         Host: {origin}\r\n\
+         Connection: close\r\n\r\n"
+    );
+    assert_eq!(received.recv()?, expected_head);
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with(&expected_head));
+
+    let (mut socket, _) = tungstenite::connect(format!("ws://{local}/pubsub"))?;
+    assert_eq!(
+        received.recv()?,
+        format!("/misty/{INSTANCE_ID}/pubsub {origin}")
+    );
+    socket.send(Message::Text("hello".into()))?;
+    assert_eq!(socket.read()?, Message::Text("hello".into()));
+    socket.close(None)?;
+
+    child.kill()?;
+    child.wait()?;
+    standin.assert_all_served();
+    Ok(())
+}
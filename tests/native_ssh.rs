@@ -0,0 +1,439 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of the built-in SSH client, against a local stand-in for sshd
+
+#![cfg(feature = "native-ssh")]
+
+mod support;
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use russh::keys::{PrivateKey, PublicKey};
+use russh::server::{Auth, Msg, Server as _, Session};
+use russh::{Channel, ChannelId};
+use russh_sftp::protocol::{Data, FileAttributes, Handle, OpenFlags, Status, StatusCode};
+
+use rerobots_cli::native_ssh;
+use support::{ssh_fixture_path, temp_path, StandIn, TestResult};
+
+const INSTANCE_ID: &str = "c9b9c1d6-69c9-4b3e-9e2e-d5d2ea8bd3f1";
+
+fn fixture_key(name: &str) -> Result<PrivateKey, Box<dyn std::error::Error>> {
+    Ok(russh::keys::load_secret_key(ssh_fixture_path(name), None)?)
+}
+
+#[derive(Clone)]
+struct Server {
+    client_key: PublicKey,
+    root: PathBuf,
+}
+
+impl russh::server::Server for Server {
+    type Handler = Connection;
+
+    fn new_client(&mut self, _: Option<std::net::SocketAddr>) -> Connection {
+        Connection {
+            client_key: self.client_key.clone(),
+            root: self.root.clone(),
+            channels: HashMap::new(),
+            shells: HashSet::new(),
+            term: None,
+        }
+    }
+}
+
+/// Connection to the stand-in
+///
+/// In exec mode, `echo ARGS` prints ARGS, and `exit N` exits with N. A shell
+/// prints the terminal type and size, then echoes input until EOF. The SFTP
+/// subsystem serves files in `root`.
+struct Connection {
+    client_key: PublicKey,
+    root: PathBuf,
+    channels: HashMap<ChannelId, Channel<Msg>>,
+    shells: HashSet<ChannelId>,
+    term: Option<String>,
+}
+
+fn exit(session: &mut Session, channel: ChannelId, code: u32) -> Result<(), russh::Error> {
+    session.exit_status_request(channel, code)?;
+    session.eof(channel)?;
+    session.close(channel)
+}
+
+impl russh::server::Handler for Connection {
+    type Error = russh::Error;
+
+    async fn auth_publickey(&mut self, user: &str, key: &PublicKey) -> Result<Auth, Self::Error> {
+        if user == "root" && *key == self.client_key {
+            Ok(Auth::Accept)
+        } else {
+            Ok(Auth::reject())
+        }
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        reply: russh::server::ChannelOpenHandle,
+        _: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.channels.insert(channel.id(), channel);
+        reply.accept().await;
+        Ok(())
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        term: &str,
+        col_width: u32,
+        row_height: u32,
+        _: u32,
+        _: u32,
+        _: &[(russh::Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.term = Some(format!("{term} {col_width}x{row_height}"));
+        session.channel_success(channel)
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        self.shells.insert(channel);
+        let term = self.term.clone().unwrap_or_else(|| "(no pty)".into());
+        session.data(channel, format!("{term}\n").into_bytes())
+    }
+
+    async fn exec_request(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        let command = String::from_utf8_lossy(data).to_string();
+        let code = match command.split_once(' ') {
+            Some(("echo", args)) => {
+                session.data(channel, format!("{args}\n").into_bytes())?;
+                0
+            }
+            Some(("exit", code)) => code.parse().unwrap_or(255),
+            _ => 127,
+        };
+        exit(session, channel, code)
+    }
+
+    async fn data(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if self.shells.contains(&channel) {
+            session.data(channel, data.to_vec())?;
+        }
+        Ok(())
+    }
+
+    async fn channel_eof(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if self.shells.remove(&channel) {
+            exit(session, channel, 0)?;
+        }
+        Ok(())
+    }
+
+    async fn subsystem_request(
+        &mut self,
+        channel: ChannelId,
+        name: &str,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        match (name, self.channels.remove(&channel)) {
+            ("sftp", Some(c)) => {
+                session.channel_success(channel)?;
+                let sftp = Sftp {
+                    root: self.root.clone(),
+                    files: HashMap::new(),
+                };
+                russh_sftp::server::run(c.into_stream(), sftp).await;
+                Ok(())
+            }
+            _ => session.channel_failure(channel),
+        }
+    }
+}
+
+struct Sftp {
+    root: PathBuf,
+    files: HashMap<String, std::fs::File>,
+}
+
+fn ok(id: u32) -> Status {
+    Status {
+        id,
+        status_code: StatusCode::Ok,
+        error_message: "Ok".into(),
+        language_tag: "en-US".into(),
+    }
+}
+
+impl russh_sftp::server::Handler for Sftp {
+    type Error = StatusCode;
+
+    fn unimplemented(&self) -> StatusCode {
+        StatusCode::OpUnsupported
+    }
+
+    async fn open(
+        &mut self,
+        id: u32,
+        filename: String,
+        pflags: OpenFlags,
+        _: FileAttributes,
+    ) -> Result<Handle, StatusCode> {
+        let file = std::fs::OpenOptions::new()
+            .read(pflags.contains(OpenFlags::READ))
+            .write(pflags.contains(OpenFlags::WRITE))
+            .create(pflags.contains(OpenFlags::CREATE))
+            .truncate(pflags.contains(OpenFlags::TRUNCATE))
+            .open(self.root.join(&filename))
+            .map_err(|_| StatusCode::NoSuchFile)?;
+        let handle = format!("{}", self.files.len());
+        self.files.insert(handle.clone(), file);
+        Ok(Handle { id, handle })
+    }
+
+    async fn close(&mut self, id: u32, handle: String) -> Result<Status, StatusCode> {
+        self.files.remove(&handle);
+        Ok(ok(id))
+    }
+
+    async fn read(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        len: u32,
+    ) -> Result<Data, StatusCode> {
+        let file = self.files.get_mut(&handle).ok_or(StatusCode::Failure)?;
+        let mut data = vec![0; len as usize];
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|_| StatusCode::Failure)?;
+        let n = file.read(&mut data).map_err(|_| StatusCode::Failure)?;
+        if n == 0 {
+            return Err(StatusCode::Eof);
+        }
+        data.truncate(n);
+        Ok(Data { id, data })
+    }
+
+    async fn write(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> Result<Status, StatusCode> {
+        let file = self.files.get_mut(&handle).ok_or(StatusCode::Failure)?;
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|_| StatusCode::Failure)?;
+        file.write_all(&data).map_err(|_| StatusCode::Failure)?;
+        Ok(ok(id))
+    }
+}
+
+/// Run the stand-in for sshd with host key from fixture `host_key`, and
+/// return its port.
+fn ssh_standin(host_key: &str, root: &Path) -> Result<u16, Box<dyn std::error::Error>> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    listener.set_nonblocking(true)?;
+    let config = Arc::new(russh::server::Config {
+        keys: vec![fixture_key(host_key)?],
+        auth_rejection_time: std::time::Duration::from_millis(10),
+        auth_rejection_time_initial: Some(std::time::Duration::ZERO),
+        ..Default::default()
+    });
+    let mut server = Server {
+        client_key: fixture_key("client_key.pem")?.public_key().clone(),
+        root: root.to_path_buf(),
+    };
+    std::thread::spawn(move || {
+        let sys = actix::System::new();
+        sys.block_on(async move {
+            let listener = tokio::net::TcpListener::from_std(listener)?;
+            server.run_on_socket(config, &listener).await
+        })
+    });
+    Ok(port)
+}
+
+fn standin_with_instance(port: u16) -> Result<StandIn, Box<dyn std::error::Error>> {
+    let mut standin = StandIn::new();
+    standin.add(serde_json::from_value(serde_json::json!({
+        "request": { "method": "GET", "path": format!("/instance/{INSTANCE_ID}") },
+        "response": {
+            "status": 200,
+            "body": {
+                "id": INSTANCE_ID,
+                "status": "READY",
+                "fwd": { "ipv4": "127.0.0.1", "port": port }
+            }
+        }
+    }))?)?;
+    Ok(standin)
+}
+
+/// Directory with the secret key as `key.pem`, and empty data directory
+fn work_dir(name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = temp_path(name);
+    std::fs::create_dir_all(dir.join("data"))?;
+    std::fs::copy(ssh_fixture_path("client_key.pem"), dir.join("key.pem"))?;
+    Ok(dir)
+}
+
+#[test]
+fn exec_native() -> TestResult {
+    let dir = work_dir("native-exec")?;
+    let port = ssh_standin("host_key", &dir)?;
+
+    let standin = standin_with_instance(port)?;
+    standin
+        .command()?
+        .current_dir(&dir)
+        .env("XDG_DATA_HOME", dir.join("data"))
+        .args(["exec", INSTANCE_ID, "--native", "--", "echo", "hello", "robot"])
+        .assert()
+        .success()
+        .stdout("hello robot\n")
+        .stderr(format!(
+            "Warning: Permanently added [127.0.0.1]:{port} (ssh-ed25519) to the list of known hosts.\n"
+        ));
+    standin.assert_all_served();
+
+    let standin = standin_with_instance(port)?;
+    standin
+        .command()?
+        .current_dir(&dir)
+        .env("XDG_DATA_HOME", dir.join("data"))
+        .args(["exec", INSTANCE_ID, "--native", "--", "exit", "3"])
+        .assert()
+        .code(3)
+        .stdout("")
+        .stderr("");
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn ssh_without_ssh_program() -> TestResult {
+    let dir = work_dir("native-ssh")?;
+    let empty_path = dir.join("bin");
+    std::fs::create_dir_all(&empty_path)?;
+    let port = ssh_standin("host_key", &dir)?;
+
+    let standin = standin_with_instance(port)?;
+    standin
+        .command()?
+        .current_dir(&dir)
+        .env("XDG_DATA_HOME", dir.join("data"))
+        .env("PATH", &empty_path)
+        .env("TERM", "xterm-test")
+        .args(["ssh", INSTANCE_ID])
+        .write_stdin("hello\n")
+        .assert()
+        .success()
+        .stdout("xterm-test 80x24\nhello\n");
+    standin.assert_all_served();
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn host_key_changed() -> TestResult {
+    let dir = work_dir("native-host-key")?;
+    let port = ssh_standin("host_key", &dir)?;
+    let other_key = fixture_key("other_host_key")?.public_key().to_openssh()?;
+    let known_hosts = dir.join("data").join("rerobots").join("known_hosts");
+    std::fs::create_dir_all(dir.join("data").join("rerobots"))?;
+    std::fs::write(&known_hosts, format!("[127.0.0.1]:{port} {other_key}\n"))?;
+
+    let standin = standin_with_instance(port)?;
+    standin
+        .command()?
+        .current_dir(&dir)
+        .env("XDG_DATA_HOME", dir.join("data"))
+        .args(["exec", INSTANCE_ID, "--native", "--", "echo", "hello"])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(format!(
+            "host key of [127.0.0.1]:{port} differs from the one in {}; \
+             if the instance was replaced, then remove the line of this host from that file\n",
+            known_hosts.display()
+        ));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn sftp() -> TestResult {
+    let dir = work_dir("native-sftp")?;
+    let remote = dir.join("remote");
+    std::fs::create_dir_all(&remote)?;
+    let port = ssh_standin("host_key", &remote)?;
+
+    let session = native_ssh::Session::connect(
+        "127.0.0.1",
+        port,
+        "root",
+        &dir.join("key.pem"),
+        &dir.join("known_hosts"),
+    )?;
+    let data: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+    std::fs::write(dir.join("upload.bin"), &data)?;
+    assert_eq!(
+        session.upload(&dir.join("upload.bin"), "copy.bin")?,
+        100_000
+    );
+    assert_eq!(std::fs::read(remote.join("copy.bin"))?, data);
+    assert_eq!(
+        session.download("copy.bin", &dir.join("download.bin"))?,
+        100_000
+    );
+    assert_eq!(std::fs::read(dir.join("download.bin"))?, data);
+    assert!(session
+        .download("missing.bin", &dir.join("missing.bin"))
+        .is_err());
+    session.close()?;
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,169 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod support;
+
+use std::net::TcpListener;
+use std::sync::mpsc;
+
+use tungstenite::Message;
+
+use support::{request_header, RecordHandshake, StandIn, TestResult};
+
+const INSTANCE_ID: &str = "c9b9c1d6-69c9-4b3e-9e2e-d5d2ea8bd3f1";
+const API_TOKEN: &str = "abc.def.ghi";
+
+/// Stand-in for the cmdsh add-on, which serves one connection
+///
+/// In exec mode, `echo ARGS` prints ARGS, and `exit N` exits with N. In pty
+/// mode, input is echoed until EOF. Every message that is received, as JSON
+/// text or binary data converted to a string, is sent to the returned
+/// channel, beginning with the authorization header.
+fn shell_standin() -> Result<(String, mpsc::Receiver<String>), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("ws://{}/", listener.local_addr()?);
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(
+        move || -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            let (stream, _) = listener.accept()?;
+            let mut socket = tungstenite::accept_hdr(
+                stream,
+                RecordHandshake::new(tx.clone(), |request| {
+                    request_header(request, "authorization").to_string()
+                }),
+            )?;
+            let mut pty = false;
+            loop {
+                match socket.read()? {
+                    Message::Text(text) => {
+                        tx.send(text.clone())?;
+                        let message: serde_json::Value = serde_json::from_str(&text)?;
+                        if message["mode"] == "pty" {
+                            pty = true;
+                        } else if let Some(command) = message["command"].as_str() {
+                            let code = match command.split_once(' ') {
+                                Some(("echo", args)) => {
+                                    socket.send(Message::Binary(format!("{args}\n").into()))?;
+                                    0
+                                }
+                                Some(("exit", code)) => code.parse()?,
+                                _ => 127,
+                            };
+                            socket.send(Message::Text(format!("{{\"exit\": {code}}}")))?;
+                        } else if pty && message["eof"] == true {
+                            socket.send(Message::Text("{\"exit\": 0}".into()))?;
+                        }
+                    }
+                    Message::Binary(data) => {
+                        tx.send(String::from_utf8(data.to_vec())?)?;
+                        if pty {
+                            socket.send(Message::Binary(data))?;
+                        }
+                    }
+                    Message::Close(_) => return Ok(()),
+                    _ => {}
+                }
+            }
+        },
+    );
+    Ok((url, rx))
+}
+
+#[test]
+fn shell_command() -> TestResult {
+    let (url, received) = shell_standin()?;
+    let mut standin = StandIn::new();
+    standin.require_token(API_TOKEN);
+    standin.add_ready_addon(
+        INSTANCE_ID,
+        "cmdsh",
+        serde_json::json!({"status": "active", "url": url}),
+    )?;
+    standin
+        .command()?
+        .env("REROBOTS_API_TOKEN", API_TOKEN)
+        .args(["shell", INSTANCE_ID, "-c", "echo hello robot"])
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout("hello robot\n");
+    standin.assert_all_served();
+    assert_eq!(received.recv()?, format!("Bearer {API_TOKEN}"));
+    let first: serde_json::Value = serde_json::from_str(&received.recv()?)?;
+    assert_eq!(
+        first,
+        serde_json::json!({"mode": "exec", "command": "echo hello robot"})
+    );
+
+    let (url, _received) = shell_standin()?;
+    let mut standin = StandIn::new();
+    standin.require_token(API_TOKEN);
+    standin.add_ready_addon(
+        INSTANCE_ID,
+        "cmdsh",
+        serde_json::json!({"status": "active", "url": url}),
+    )?;
+    standin
+        .command()?
+        .env("REROBOTS_API_TOKEN", API_TOKEN)
+        .args(["shell", INSTANCE_ID, "-c", "exit 3"])
+        .write_stdin("")
+        .assert()
+        .code(3)
+        .stdout("");
+    Ok(())
+}
+
+#[test]
+fn shell_interactive() -> TestResult {
+    let (url, received) = shell_standin()?;
+    let mut standin = StandIn::new();
+    standin.require_token(API_TOKEN);
+    standin.add_ready_addon(
+        INSTANCE_ID,
+        "cmdsh",
+        serde_json::json!({"status": "active", "url": url}),
+    )?;
+    standin
+        .command()?
+        .env("REROBOTS_API_TOKEN", API_TOKEN)
+        .env("TERM", "xterm-256color")
+        .args(["shell", INSTANCE_ID])
+        .write_stdin("uname -a\n")
+        .assert()
+        .success()
+        .stdout("uname -a\n");
+    standin.assert_all_served();
+    received.recv()?;
+    let first: serde_json::Value = serde_json::from_str(&received.recv()?)?;
+    assert_eq!(
+        first,
+        serde_json::json!({"mode": "pty", "cols": 80, "rows": 24, "term": "xterm-256color"})
+    );
+    assert_eq!(received.recv()?, "uname -a\n");
+    assert_eq!(received.recv()?, r#"{"eof":true}"#);
+    Ok(())
+}
+
+#[test]
+fn shell_requires_ready() -> TestResult {
+    let standin = StandIn::replay("isready")?;
+    standin
+        .command()?
+        .args(["shell", INSTANCE_ID])
+        .assert()
+        .code(1)
+        .stderr("Error: instance is not READY\n");
+    Ok(())
+}
@@ -0,0 +1,294 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local stand-in for the rerobots API
+//!
+//! Fixtures in `tests/fixtures/` are recorded exchanges, in the format of
+//! `rerobots_cli::fixture`, and are replayed in the order given. Record new
+//! fixtures by running `rerobots` with the environment variable
+//! `REROBOTS_RECORD` set to the path of the file to create.
+
+#![allow(dead_code)]
+
+use assert_cmd::Command;
+use tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
+
+use rerobots_cli::fixture;
+
+pub type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+pub struct StandIn {
+    server: mockito::ServerGuard,
+    mocks: Vec<mockito::Mock>,
+    api_token: Option<String>,
+}
+
+impl StandIn {
+    pub fn new() -> StandIn {
+        StandIn {
+            server: mockito::Server::new(),
+            mocks: vec![],
+            api_token: None,
+        }
+    }
+
+    /// Serve exchanges that are added later only if `api_token` is given.
+    pub fn require_token(&mut self, api_token: &str) {
+        self.api_token = Some(api_token.into());
+    }
+
+    /// Serve the exchanges in `tests/fixtures/{name}.json`.
+    ///
+    /// Each exchange is served once. After all exchanges matching a request
+    /// have been served, the last of them is repeated.
+    pub fn replay(name: &str) -> Result<StandIn, Box<dyn std::error::Error>> {
+        StandIn::new().and_replay(name)
+    }
+
+    /// Add the exchanges in `tests/fixtures/{name}.json`.
+    pub fn and_replay(mut self, name: &str) -> Result<StandIn, Box<dyn std::error::Error>> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(format!("{name}.json"));
+        for exchange in fixture::load(&path)? {
+            self.add(exchange)?;
+        }
+        Ok(self)
+    }
+
+    pub fn add(&mut self, exchange: fixture::Exchange) -> TestResult {
+        let mut mock = self
+            .server
+            .mock(
+                exchange.request.method.as_str(),
+                exchange.request.path.as_str(),
+            )
+            .with_status(exchange.response.status as usize)
+            .expect(1);
+        if let Some(api_token) = &self.api_token {
+            mock = mock.match_header("authorization", format!("Bearer {api_token}").as_str());
+        }
+        if let Some(body) = exchange.request.body {
+            mock = mock.match_body(mockito::Matcher::Json(body));
+        }
+        if let Some(body) = exchange.response.body {
+            mock = mock
+                .with_header("content-type", "application/json")
+                .with_body(serde_json::to_string(&body)?);
+        }
+        self.mocks.push(mock.create());
+        Ok(())
+    }
+
+    /// Stand-in that serves details of a READY instance, and then status of
+    /// its add-on, as for [`StandIn::add_ready_addon()`]
+    pub fn with_ready_addon(
+        instance_id: &str,
+        addon: &str,
+        status: serde_json::Value,
+    ) -> Result<StandIn, Box<dyn std::error::Error>> {
+        let mut standin = StandIn::new();
+        standin.add_ready_addon(instance_id, addon, status)?;
+        Ok(standin)
+    }
+
+    /// Serve details of a READY instance, and then `status` of its add-on,
+    /// e.g., `{"status": "active"}`.
+    pub fn add_ready_addon(
+        &mut self,
+        instance_id: &str,
+        addon: &str,
+        status: serde_json::Value,
+    ) -> TestResult {
+        self.add(serde_json::from_value(serde_json::json!({
+            "request": { "method": "GET", "path": format!("/instance/{instance_id}") },
+            "response": {
+                "status": 200,
+                "body": { "id": instance_id, "status": "READY" }
+            }
+        }))?)?;
+        self.add_addon(instance_id, addon, status)
+    }
+
+    /// Serve `status` of add-on of instance.
+    pub fn add_addon(
+        &mut self,
+        instance_id: &str,
+        addon: &str,
+        status: serde_json::Value,
+    ) -> TestResult {
+        self.add(serde_json::from_value(serde_json::json!({
+            "request": { "method": "GET", "path": format!("/addon/{addon}/{instance_id}") },
+            "response": { "status": 200, "body": status }
+        }))?)
+    }
+
+    /// Stand-in that serves the secret key of instance
+    pub fn with_sshkey(
+        instance_id: &str,
+        key: &str,
+    ) -> Result<StandIn, Box<dyn std::error::Error>> {
+        let mut standin = StandIn::new();
+        standin.add_sshkey(instance_id, key)?;
+        Ok(standin)
+    }
+
+    /// Serve the secret key of instance.
+    pub fn add_sshkey(&mut self, instance_id: &str, key: &str) -> TestResult {
+        self.add(serde_json::from_value(serde_json::json!({
+            "request": { "method": "GET", "path": format!("/instance/{instance_id}/sshkey") },
+            "response": { "status": 200, "body": { "key": key } }
+        }))?)
+    }
+
+    /// Serve `method path` once, with the response computed from the request body.
+    pub fn add_responder<F>(&mut self, method: &str, path: &str, respond: F)
+    where
+        F: Fn(serde_json::Value) -> serde_json::Value + Send + Sync + 'static,
+    {
+        let mock = self
+            .server
+            .mock(method, path)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body_from_request(move |request| {
+                let body = request
+                    .body()
+                    .ok()
+                    .and_then(|b| serde_json::from_slice(b).ok())
+                    .unwrap_or(serde_json::Value::Null);
+                respond(body).to_string().into_bytes()
+            })
+            .expect(1);
+        self.mocks.push(mock.create());
+    }
+
+    pub fn url(&self) -> String {
+        self.server.url()
+    }
+
+    /// Command to run `rerobots` with this stand-in as the API origin
+    pub fn command(&self) -> Result<Command, Box<dyn std::error::Error>> {
+        Ok(Command::from_std(self.std_command()))
+    }
+
+    /// As [`StandIn::command()`], for running in the background
+    pub fn std_command(&self) -> std::process::Command {
+        let mut cmd = std::process::Command::new(assert_cmd::cargo::cargo_bin("rerobots"));
+        cmd.env("REROBOTS_ORIGIN", self.url())
+            .env("REROBOTS_CONFIG", temp_path("no-config.yaml"))
+            .env("REROBOTS_CREDENTIAL_BACKEND", "file")
+            .env("XDG_DATA_HOME", temp_path("no-data"))
+            .env_remove("REROBOTS_API_TOKEN")
+            .env_remove("REROBOTS_CREDENTIALS_PASSPHRASE")
+            .env_remove("REROBOTS_PROFILE")
+            .env_remove("REROBOTS_RECORD")
+            .env_remove("RUST_LOG")
+            .env_remove("SSH_AUTH_SOCK");
+        cmd
+    }
+
+    /// Panic unless every exchange was served.
+    pub fn assert_all_served(&self) {
+        for mock in self.mocks.iter() {
+            mock.assert();
+        }
+    }
+}
+
+fn encode_base64url(data: &[u8]) -> String {
+    openssl::base64::encode_block(data)
+        .trim_end_matches('=')
+        .replace('+', "-")
+        .replace('/', "_")
+}
+
+/// API token with the given claims and a signature that is not valid
+pub fn make_token(claims: serde_json::Value) -> String {
+    format!(
+        "{}.{}.c2lnbmF0dXJl",
+        encode_base64url(br#"{"alg":"ES256","typ":"JWT"}"#),
+        encode_base64url(claims.to_string().as_bytes())
+    )
+}
+
+/// API token with the given claims, signed using RS256
+pub fn make_signed_token(
+    claims: serde_json::Value,
+    key: &openssl::pkey::PKey<openssl::pkey::Private>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let signed_part = format!(
+        "{}.{}",
+        encode_base64url(br#"{"alg":"RS256","typ":"JWT"}"#),
+        encode_base64url(claims.to_string().as_bytes())
+    );
+    let mut signer = openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), key)?;
+    signer.update(signed_part.as_bytes())?;
+    Ok(format!(
+        "{signed_part}.{}",
+        encode_base64url(&signer.sign_to_vec()?)
+    ))
+}
+
+/// Unix time, in seconds
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Path for a temporary file that is unique to the calling test
+/// WebSocket handshake callback that sends a description of the request, as
+/// made by `describe`, to the channel
+pub struct RecordHandshake<F> {
+    sender: std::sync::mpsc::Sender<String>,
+    describe: F,
+}
+
+impl<F: FnOnce(&Request) -> String> RecordHandshake<F> {
+    pub fn new(sender: std::sync::mpsc::Sender<String>, describe: F) -> RecordHandshake<F> {
+        RecordHandshake { sender, describe }
+    }
+}
+
+impl<F: FnOnce(&Request) -> String> Callback for RecordHandshake<F> {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        let _ = self.sender.send((self.describe)(request));
+        Ok(response)
+    }
+}
+
+/// Value of header in request, or `(none)`
+pub fn request_header<'a>(request: &'a Request, name: &str) -> &'a str {
+    request
+        .headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("(none)")
+}
+
+/// Path of file in `tests/fixtures/ssh/`, e.g., `client_key.pem`
+pub fn ssh_fixture_path(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("ssh")
+        .join(name)
+}
+
+pub fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("rerobots-{}-{name}", std::process::id()))
+}
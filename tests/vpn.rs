@@ -0,0 +1,157 @@
+// Copyright (C) 2026 rerobots, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod support;
+
+use support::{temp_path, StandIn, TestResult};
+
+const INSTANCE_ID: &str = "c9b9c1d6-69c9-4b3e-9e2e-d5d2ea8bd3f1";
+
+#[test]
+fn create_client() -> TestResult {
+    let standin = StandIn::replay("vpn_create_client")?;
+    let path = temp_path("laptop.ovpn");
+    standin
+        .command()?
+        .args(["vpn", INSTANCE_ID, "create-client", "laptop", "-f"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(format!(
+            "Wrote configuration of VPN client laptop to {}\n",
+            path.display()
+        ));
+    standin.assert_all_served();
+    assert!(std::fs::read_to_string(&path)?.starts_with("client\ndev tun\n"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path)?.permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn list_and_revoke() -> TestResult {
+    let standin = StandIn::replay("vpn_list")?;
+    standin
+        .command()?
+        .args(["vpn", INSTANCE_ID, "list"])
+        .assert()
+        .success()
+        .stdout("laptop\nci\n");
+
+    let standin = StandIn::replay("vpn_revoke")?;
+    standin
+        .command()?
+        .args(["vpn", INSTANCE_ID, "revoke", "laptop"])
+        .assert()
+        .success()
+        .stdout("");
+    standin.assert_all_served();
+    Ok(())
+}
+
+/// Directory with a stand-in for `openvpn` that runs the given script
+#[cfg(unix)]
+fn fake_openvpn(
+    name: &str,
+    script: &str,
+) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin_dir = temp_path(name);
+    std::fs::create_dir_all(&bin_dir)?;
+    let openvpn = bin_dir.join("openvpn");
+    std::fs::write(&openvpn, format!("#!/bin/sh\n{script}"))?;
+    std::fs::set_permissions(&openvpn, std::fs::Permissions::from_mode(0o755))?;
+    Ok(bin_dir)
+}
+
+#[cfg(unix)]
+#[test]
+fn up_with_config() -> TestResult {
+    let bin_dir = fake_openvpn("vpn-up-bin", "echo \"$@\"\nexit 4\n")?;
+    let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH")?);
+    let standin = StandIn::new();
+    standin
+        .command()?
+        .env("PATH", &path)
+        .args(["vpn", INSTANCE_ID, "up", "--config", "lab.ovpn"])
+        .assert()
+        .code(4)
+        .stdout("--config lab.ovpn\n");
+    std::fs::remove_dir_all(&bin_dir)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn up_temporary_client() -> TestResult {
+    let marker = temp_path("vpn-up-marker");
+    let config_marker = temp_path("vpn-up-config");
+    let bin_dir = fake_openvpn(
+        "vpn-up-temporary-bin",
+        &format!(
+            "trap 'echo stopped >> {marker}; exit 0' TERM\n\
+             echo \"$2\" > {config_marker}\n\
+             cat \"$2\" | head -n 1 > {marker}\n\
+             while true; do sleep 0.1; done\n",
+            marker = marker.display(),
+            config_marker = config_marker.display()
+        ),
+    )?;
+    let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH")?);
+    let standin = StandIn::replay("vpn_create_client")?.and_replay("vpn_revoke")?;
+    let mut child = standin
+        .std_command()
+        .env("PATH", &path)
+        .args(["vpn", INSTANCE_ID, "up", "--name", "laptop"])
+        .spawn()?;
+
+    let start = std::time::Instant::now();
+    while !marker.exists() {
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    std::process::Command::new("kill")
+        .arg("-TERM")
+        .arg(child.id().to_string())
+        .status()?;
+    assert!(child.wait()?.success());
+    assert_eq!(std::fs::read_to_string(&marker)?, "client\nstopped\n");
+    standin.assert_all_served();
+
+    // The configuration was in a private directory, which is removed
+    let config = std::path::PathBuf::from(std::fs::read_to_string(&config_marker)?.trim());
+    let config_dir = config
+        .parent()
+        .ok_or("configuration should be in directory")?;
+    assert_eq!(
+        config.file_name(),
+        Some(std::ffi::OsStr::new(&format!("rrvpn{}.ovpn", child.id())))
+    );
+    assert!(config_dir
+        .file_name()
+        .is_some_and(|d| d.to_string_lossy().starts_with("rerobots-vpn")));
+    assert!(!config_dir.exists());
+
+    std::fs::remove_file(&marker)?;
+    std::fs::remove_file(&config_marker)?;
+    std::fs::remove_dir_all(&bin_dir)?;
+    Ok(())
+}